@@ -1,7 +1,9 @@
+use std::time::Instant;
+
 use clap::Parser;
 use log::LevelFilter;
 
-use robmcf_greedy::{Network, Options};
+use robmcf_greedy::{LoadShading, Network, NetworkFormat, Options, SolverError};
 
 mod util;
 use util::*;
@@ -17,19 +19,81 @@ fn main() {
         LevelFilter::Debug
     } else {
         match args.command {
-            Commands::Benchmark { .. } => LevelFilter::Error,
+            Commands::Benchmark { .. } | Commands::Tune { .. } => LevelFilter::Error,
             _ => LevelFilter::Info,
         }
     };
     setup_logger(log_level);
 
-    let options = Options {
-        cost_fn: args.costs,
-        delta_fn: args.delta,
-        relative_draw_fn: args.draw,
-        slack_fn: args.slack,
-        remainder_solve_method: args.remainder,
+    let mut options = match &args.config {
+        Some(config) => match Options::from_file(config) {
+            Ok(options) => options,
+            Err(e) => {
+                log::error!("{e}");
+                return;
+            }
+        },
+        None => solver_options(&args),
     };
+    options.preprocessing_cache = args.cache.clone();
+
+    if let Commands::Tune {
+        directory,
+        output,
+        strategy,
+        budget,
+        cost_tolerance,
+    } = &args.command
+    {
+        let networks = match load_training_networks(&options, directory) {
+            Ok(networks) => networks,
+            Err(e) => {
+                log::error!("{e}");
+                return;
+            }
+        };
+        match run_tuning(&networks, &options, strategy, *budget, *cost_tolerance) {
+            Some((best, score)) => {
+                println!(
+                    "Best configuration (mean robustness coefficient {:4.3}): {:?}",
+                    score, best
+                );
+                attempt!(best.serialize(output));
+            }
+            None => log::error!(
+                "No configuration solved all training networks within the cost tolerance."
+            ),
+        }
+        return;
+    }
+
+    if let Commands::Compare {
+        first,
+        second,
+        json,
+        csv,
+    } = &args.command
+    {
+        let comparison = Network::from_file(&options, first).and_then(|first| match second {
+            Some(second) => first.compare_to(&Network::from_file(&options, second)?),
+            None => first.compare_to_baseline(),
+        });
+        let comparison = match comparison {
+            Ok(comparison) => comparison,
+            Err(e) => {
+                log::error!("{e}");
+                return;
+            }
+        };
+        if let Some(json) = json {
+            attempt!(comparison.serialize(json));
+        }
+        if let Some(csv) = csv {
+            attempt!(comparison.export(csv));
+        }
+        println!("{comparison}");
+        return;
+    }
 
     let network = match &args.command {
         Commands::Random {
@@ -57,8 +121,29 @@ fn main() {
         )),
         Commands::Benchmark { file, .. } => Network::from_file(&options, file),
         Commands::Solve { file, .. } => Network::from_file(&options, file),
-        Commands::Ilp { file } => Network::from_file(&options, file),
+        Commands::Ilp { file, .. } => Network::from_file(&options, file),
         Commands::Latex { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Graphml { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Dot { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Geojson { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Svg { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Dimacs { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::ImportDimacs { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Disrupt { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::Convert { in_file, .. } => Network::from_file(&options, in_file),
+        Commands::ImportGtfs {
+            feed,
+            route_types,
+            per_service,
+            ..
+        } => Network::from_gtfs(&options, feed, route_types, *per_service),
+        Commands::ImportOsm {
+            extract,
+            track_capacity,
+            ..
+        } => Network::from_osm(&options, extract, *track_capacity),
+        Commands::ImportGraphml { file, .. } => Network::from_graphml(&options, file),
+        Commands::Tune { .. } | Commands::Compare { .. } => unreachable!(),
     };
 
     let mut network = match network {
@@ -69,104 +154,240 @@ fn main() {
         }
     };
 
-    let (output, lower_bound, original_flow, penalty_arcs, fix_best) = match &args.command {
-        Commands::Benchmark { iterations, .. } => {
-            attempt!(network.validate_network());
-            let (network, time_preprocess, time_solve) = run_benchmark(&network, *iterations);
-            if let Some(export) = args.export {
-                attempt!(network.export(&export, Some(time_preprocess), Some(time_solve)));
-            };
-            return;
-        }
-        Commands::Latex {
-            out_file,
-            no_text,
-            width,
-            mark_stations,
-            ..
-        } => {
-            attempt!(network.to_latex(out_file, *no_text, *width, *mark_stations));
-            return;
-        }
-        Commands::Ilp { .. } => {
-            network.options.remainder_solve_method = robmcf_greedy::RemainderSolveMethod::Ilp;
-            attempt!(network.solve_full_ilp());
-            println!("{}", network);
-            return;
-        }
-        Commands::Random {
-            output,
-            lower_bound,
-            original_flow,
-            penalty_arcs,
-            random,
-            ..
-        } => (
-            output,
-            lower_bound,
-            original_flow,
-            penalty_arcs,
-            random.fix_best,
-        ),
-        Commands::Solve {
-            randomize_capacities,
-            randomize_costs,
-            randomize_scenarios,
-            randomize_fixed_arcs,
-            random,
-            output,
-            lower_bound,
-            original_flow,
-            penalty_arcs,
-            override_fixed,
-            override_costs,
-            ..
-        } => {
-            if *randomize_capacities {
-                network.randomize_capacities(random.arc_density, random.umin, random.umax);
-            }
-            if *randomize_costs {
-                network.randomize_costs(random.cmin, random.cmax);
-            }
-            if *randomize_scenarios {
-                network.randomize_scenarios(
-                    random.scenarios,
-                    random.supply_density_min,
-                    random.supply_density_max,
-                    random.bmin,
-                    random.bmax,
-                );
+    let (output, lower_bound, original_flow, penalty_arcs, penalty_cost, penalty_costs, fix_best) =
+        match &args.command {
+            Commands::Benchmark { iterations, .. } => {
+                attempt!(network.validate_network());
+                let (network, time_preprocess, time_solve) = run_benchmark(&network, *iterations);
+                if let Some(export) = args.export {
+                    attempt!(network.export(&export, Some(time_preprocess), Some(time_solve)));
+                };
+                return;
+            }
+            Commands::Tune { .. } | Commands::Compare { .. } => unreachable!(),
+            Commands::Disrupt {
+                out_file,
+                method,
+                scenarios,
+                failures,
+                perturbation,
+                ..
+            } => {
+                attempt!(network.disrupt_scenarios(method, *scenarios, *failures, *perturbation));
+                attempt!(network.validate_network());
+                attempt!(network.serialize(out_file));
+                return;
             }
-            if *randomize_fixed_arcs {
-                network.randomize_fixed_arcs(
-                    random.fixed,
-                    random.fixed_consecutive,
-                    random.existing_only,
+            Commands::ImportGtfs { out_file, .. }
+            | Commands::ImportOsm { out_file, .. }
+            | Commands::ImportGraphml { out_file, .. } => {
+                log::info!(
+                    "Imported {} vertices and {} scenarios.",
+                    network.vertices.len(),
+                    network.balances.len()
                 );
+                attempt!(network.serialize(out_file));
+                return;
             }
-            if let Some(r#override) = override_fixed {
-                network.fixed_arcs = vec![];
-                for &(s, t) in r#override.iter() {
-                    if s >= network.vertices.len() || t >= network.vertices.len() {
-                        log::error!(
-                            "{}",
-                            robmcf_greedy::SolverError::NetworkShapeError(format!(
-                                "Attempted to fix arc ({},{}), but only {} vertices exist.",
-                                s,
-                                t,
-                                network.vertices.len()
-                            ))
-                        );
-                        return;
+            Commands::Convert {
+                in_file,
+                out_file,
+                format,
+            } => {
+                let format = match format {
+                    Some(format) => *format,
+                    None => {
+                        let in_format = Network::format_of(in_file);
+                        attempt!(&in_format);
+                        match in_format {
+                            Ok(NetworkFormat::Sparse) => NetworkFormat::Dense,
+                            _ => NetworkFormat::Sparse,
+                        }
                     }
-
-                    network.fixed_arcs.push((s, t));
+                };
+                attempt!(network.serialize_as(out_file, format));
+                return;
+            }
+            Commands::Latex {
+                out_file,
+                no_text,
+                width,
+                mark_stations,
+                figure,
+                table,
+                ..
+            } => {
+                attempt!(network.to_latex(out_file, *figure, *no_text, *width, *mark_stations));
+                if let Some(table) = table {
+                    attempt!(network.to_latex_table(table));
                 }
+                return;
             }
-            if let Some(r#override) = override_costs {
-                for &(s, t, c) in r#override.iter() {
-                    if s >= network.vertices.len() || t >= network.vertices.len() {
-                        log::error!(
+            Commands::Graphml {
+                out_file, loads, ..
+            } => {
+                attempt!(network.to_graphml(out_file, *loads));
+                return;
+            }
+            Commands::Dot {
+                out_file, loads, ..
+            } => {
+                attempt!(network.to_dot(out_file, *loads));
+                return;
+            }
+            Commands::Geojson {
+                out_file,
+                crs,
+                origin,
+                ..
+            } => {
+                attempt!(network.to_geojson(out_file, crs.as_deref(), *origin));
+                return;
+            }
+            Commands::Svg {
+                out_file,
+                width,
+                scenario,
+                consistent_flows,
+                ..
+            } => {
+                let shading = match (scenario, consistent_flows) {
+                    (Some(lambda), _) => LoadShading::Scenario(*lambda),
+                    (None, true) => LoadShading::ConsistentFlows,
+                    (None, false) => LoadShading::None,
+                };
+                attempt!(network.to_svg(out_file, *width, shading));
+                return;
+            }
+            Commands::Dimacs {
+                out_file,
+                scenario,
+                multi_commodity,
+                ..
+            } => {
+                attempt!(network.to_dimacs(out_file, *scenario, *multi_commodity));
+                return;
+            }
+            Commands::ImportDimacs {
+                out_file,
+                solutions,
+                ..
+            } => {
+                for (lambda, solution) in solutions.iter().enumerate() {
+                    attempt!(network.add_dimacs_solution(solution, lambda));
+                }
+                attempt!(network.validate_solution());
+                attempt!(network.serialize(out_file));
+                if let Some(export) = args.export {
+                    attempt!(network.export(&export, None, None));
+                };
+                println!("{}", network);
+                return;
+            }
+            Commands::Ilp {
+                warm_start_from_greedy,
+                format,
+                ..
+            } => {
+                let start_solve = Instant::now();
+                if *warm_start_from_greedy {
+                    // the greedy has to route all supply for a complete starting solution
+                    network.options.remainder_solve_method =
+                        robmcf_greedy::RemainderSolveMethod::Greedy;
+                    attempt_solve!(network, *format, network.preprocess());
+                    if let Err(e) = network.solve() {
+                        log::warn!("Could not find a greedy solution to start from: {e}");
+                    }
+                }
+                network.options.remainder_solve_method = robmcf_greedy::RemainderSolveMethod::Ilp;
+                match network.solutions.is_some() && *warm_start_from_greedy {
+                    true => attempt_solve!(network, *format, network.warm_start_full_ilp()),
+                    false => attempt_solve!(network, *format, network.solve_full_ilp()),
+                }
+                attempt_solve!(network, *format, network.validate_solution());
+                let time_solve = start_solve.elapsed().as_millis() as usize;
+                print_result(&network, *format, None, Some(time_solve));
+                return;
+            }
+            Commands::Random {
+                output,
+                lower_bound,
+                original_flow,
+                penalty_arcs,
+                penalty_cost,
+                penalty_costs,
+                random,
+                ..
+            } => (
+                output,
+                lower_bound,
+                original_flow,
+                penalty_arcs,
+                penalty_cost,
+                penalty_costs,
+                random.fix_best,
+            ),
+            Commands::Solve {
+                randomize_capacities,
+                randomize_costs,
+                randomize_scenarios,
+                randomize_fixed_arcs,
+                random,
+                output,
+                lower_bound,
+                original_flow,
+                penalty_arcs,
+                penalty_cost,
+                penalty_costs,
+                override_fixed,
+                override_costs,
+                ..
+            } => {
+                if *randomize_capacities {
+                    network.randomize_capacities(random.arc_density, random.umin, random.umax);
+                }
+                if *randomize_costs {
+                    network.randomize_costs(random.cmin, random.cmax);
+                }
+                if *randomize_scenarios {
+                    network.randomize_scenarios(
+                        random.scenarios,
+                        random.supply_density_min,
+                        random.supply_density_max,
+                        random.bmin,
+                        random.bmax,
+                    );
+                }
+                if *randomize_fixed_arcs {
+                    network.randomize_fixed_arcs(
+                        random.fixed,
+                        random.fixed_consecutive,
+                        random.existing_only,
+                    );
+                }
+                if let Some(r#override) = override_fixed {
+                    network.fixed_arcs = vec![];
+                    for &(s, t) in r#override.iter() {
+                        if s >= network.vertices.len() || t >= network.vertices.len() {
+                            log::error!(
+                                "{}",
+                                robmcf_greedy::SolverError::NetworkShapeError(format!(
+                                    "Attempted to fix arc ({},{}), but only {} vertices exist.",
+                                    s,
+                                    t,
+                                    network.vertices.len()
+                                ))
+                            );
+                            return;
+                        }
+
+                        network.fixed_arcs.push((s, t));
+                    }
+                }
+                if let Some(r#override) = override_costs {
+                    for &(s, t, c) in r#override.iter() {
+                        if s >= network.vertices.len() || t >= network.vertices.len() {
+                            log::error!(
                             "{}",
                             robmcf_greedy::SolverError::NetworkShapeError(format!(
                                 "Attempted to set cost for arc at indices ({},{}), but only {} vertices exist.",
@@ -175,45 +396,61 @@ fn main() {
                                 network.vertices.len()
                             ))
                         );
-                        return;
-                    }
+                            return;
+                        }
 
-                    network.costs.set(s, t, c);
+                        network.costs.set(s, t, c);
+                    }
                 }
+                if let Some(file) = output {
+                    attempt!(network.serialize(file));
+                }
+                (
+                    output,
+                    lower_bound,
+                    original_flow,
+                    penalty_arcs,
+                    penalty_cost,
+                    penalty_costs,
+                    random.fix_best,
+                )
             }
-            if let Some(file) = output {
-                attempt!(network.serialize(file));
-            }
-            (
-                output,
-                lower_bound,
-                original_flow,
-                penalty_arcs,
-                random.fix_best,
-            )
-        }
+        };
+
+    let format = match &args.command {
+        Commands::Solve { format, .. } => *format,
+        _ => OutputFormat::Text,
     };
 
-    attempt!(network.validate_network());
+    // Penalty arcs go first, since they can make otherwise infeasible scenarios routable
+    if *penalty_arcs {
+        attempt_solve!(
+            network,
+            format,
+            network.add_penalty_arcs(*penalty_cost, penalty_costs.as_deref().unwrap_or_default())
+        );
+    }
+    attempt_solve!(network, format, network.validate_network());
     if let Some(output) = output {
         attempt!(network.serialize(output));
     }
-    if *penalty_arcs {
-        attempt!(network.add_penalty_arcs());
-    }
     if *lower_bound {
-        attempt!(network.lower_bound());
+        attempt_solve!(network, format, network.lower_bound());
     }
     if *original_flow {
-        attempt!(network.original_flow());
+        attempt_solve!(network, format, network.original_flow());
     }
     if let Some(number) = fix_best {
-        attempt!(network.fix_best_candidates(number));
+        attempt_solve!(network, format, network.fix_best_candidates(number));
     }
-    attempt!(network.preprocess());
-    attempt!(network.solve());
-    attempt!(network.solve_remainder());
-    attempt!(network.validate_solution());
+    let start_preprocess = Instant::now();
+    attempt_solve!(network, format, network.preprocess());
+    let time_preprocess = start_preprocess.elapsed().as_millis() as usize;
+    let start_solve = Instant::now();
+    attempt_solve!(network, format, network.solve());
+    attempt_solve!(network, format, network.solve_remainder());
+    let time_solve = start_solve.elapsed().as_millis() as usize;
+    attempt_solve!(network, format, network.validate_solution());
     if let Some(output) = output {
         // Second time to also capture the baseline/solution
         attempt!(network.serialize(output));
@@ -222,5 +459,68 @@ fn main() {
         attempt!(network.export(&export, None, None));
     };
 
-    println!("{}", network);
+    print_result(&network, format, Some(time_preprocess), Some(time_solve));
+}
+
+/// Solver parameters as given on the command line.
+fn solver_options(args: &Args) -> Options {
+    Options {
+        cost_fn: args.costs.clone(),
+        delta_fn: args.delta.clone(),
+        relative_draw_fn: args.draw.clone(),
+        slack_fn: args.slack.clone(),
+        remainder_solve_method: args.remainder.clone(),
+        routing_metric: args.routing_metric.clone(),
+        objective_fn: args.objective.clone(),
+        objective_metrics: args.objective_metrics.clone(),
+        objective_weights: args.objective_weights.clone(),
+        continuous_flows: args.continuous,
+        preprocessing_cache: None,
+    }
+}
+
+/// Prints the solved network, either for humans or for scripts.
+fn print_result(
+    network: &Network,
+    format: OutputFormat,
+    time_preprocess: Option<usize>,
+    time_solve: Option<usize>,
+) {
+    match format {
+        OutputFormat::Text => println!("{}", network),
+        OutputFormat::Json => attempt!(network
+            .result_json(time_preprocess, time_solve, None)
+            .map(|json| println!("{json}"))),
+        OutputFormat::Csv => attempt!(network.result_csv().map(|csv| print!("{csv}"))),
+    }
+}
+
+/// Prints the status and error of a failed solve for scripts reading JSON results. The other
+/// formats have no place for it, so the logged error has to do.
+fn print_failure(network: &Network, format: OutputFormat, error: &SolverError) {
+    if let OutputFormat::Json = format {
+        match network.result_json(None, None, Some(error)) {
+            Ok(json) => println!("{json}"),
+            Err(e) => log::error!("{e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_examples_with_default_options() {
+        for example in ["chapter_3", "chapter_5", "greedy_optimal", "network"] {
+            let file = format!("../examples/{example}.json");
+            let args = Args::try_parse_from(["cli", "solve", &file]).unwrap();
+            let mut network = Network::from_file(&solver_options(&args), &file).unwrap();
+            network.validate_network().unwrap();
+            network.preprocess().unwrap();
+            network.solve().unwrap();
+            network.solve_remainder().unwrap();
+            network.validate_solution().unwrap();
+        }
+    }
 }
@@ -1,6 +1,7 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use robmcf_greedy::{
-    CostFunction, DeltaFunction, RelativeDrawFunction, RemainderSolveMethod, SlackFunction,
+    CostFunction, DeltaFunction, DisruptionMethod, LatexFigure, NetworkFormat, ObjectiveFunction,
+    RelativeDrawFunction, RemainderSolveMethod, SlackFunction,
 };
 
 /// CLI for the Greedy RobMCF solver library.
@@ -45,6 +46,50 @@ pub(crate) struct Args {
     /// [M]ethod by which a solution for routing supply which cannot use fixed arcs is found
     #[arg(long, short='m', value_enum, default_value_t = RemainderSolveMethod::None, global = true, display_order = 15, help_heading="Solver Parameters")]
     pub(crate) remainder: RemainderSolveMethod,
+
+    /// Cost metric which the greedy routes by and in which the cost of a solution is reported
+    #[arg(
+        long,
+        default_value = "costs",
+        global = true,
+        display_order = 16,
+        help_heading = "Solver Parameters"
+    )]
+    pub(crate) routing_metric: String,
+
+    /// How the ILPs combine several cost metrics into a single objective
+    #[arg(long, value_enum, default_value_t = ObjectiveFunction::Single, global = true, display_order = 17, help_heading="Solver Parameters")]
+    pub(crate) objective: ObjectiveFunction,
+
+    /// Cost metrics minimized by lexicographic or weighted objectives, in order. Defaults to all metrics.
+    #[arg(long, value_delimiter = ',', num_args = 1.., global = true, display_order = 18, help_heading="Solver Parameters")]
+    pub(crate) objective_metrics: Vec<String>,
+
+    /// Weights of the objective metrics for weighted objectives. Defaults to 1 for each.
+    #[arg(long, value_delimiter = ',', num_args = 1.., global = true, display_order = 19, help_heading="Solver Parameters")]
+    pub(crate) objective_weights: Vec<f64>,
+
+    /// Solve the ILPs with continuous flow variables, rounding the paths of each OD pair to whole quanta afterwards
+    #[arg(
+        long,
+        global = true,
+        display_order = 20,
+        help_heading = "Solver Parameters"
+    )]
+    pub(crate) continuous: bool,
+
+    /// Load all solver parameters from a config file, e.g. one created by "tune". Takes precedence over the other solver parameters.
+    #[arg(
+        long,
+        global = true,
+        display_order = 21,
+        help_heading = "Solver Parameters"
+    )]
+    pub(crate) config: Option<String>,
+
+    /// Cache preprocessing results in the given directory, and reuse them for networks and options which lead to the same results
+    #[arg(long, global = true, display_order = 5)]
+    pub(crate) cache: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -205,6 +250,14 @@ pub(crate) enum Commands {
         #[arg(long, short = 'p', global = true, display_order = 1)]
         penalty_arcs: bool,
 
+        /// Cost of routing a unit of supply over a penalty arc. Defaults to more than any path through the network
+        #[arg(long, global = true, display_order = 1, requires = "penalty_arcs")]
+        penalty_cost: Option<usize>,
+
+        /// Override penalty costs per OD pair. Pass triples of "s,t,cost"
+        #[arg(long, global = true, display_order = 1, requires = "penalty_arcs", value_parser = parse_triplet, num_args=1..)]
+        penalty_costs: Option<Vec<(usize, usize, usize)>>,
+
         /// Enable capacity randomization
         #[arg(long, display_order = 100, help_heading = "Random Capacities")]
         randomize_capacities: bool,
@@ -231,11 +284,23 @@ pub(crate) enum Commands {
         /// Override fixed arcs. Pass tuples of "s,t"
         #[arg(long, display_order = 404, help_heading = "Random Fixed Arcs", value_parser = parse_tuple, num_args=1..)]
         override_fixed: Option<Vec<(usize, usize)>>,
+
+        /// Format of the result printed at the end
+        #[arg(long, value_enum, display_order = 0, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
     /// Attempt to solve the entire network via an ILP. No greedy involvement.
     Ilp {
         /// Path to a file containing a network to be used as input.
         file: String,
+
+        /// Solve the network greedily first, and pass the solution to Gurobi as a starting point
+        #[arg(long, display_order = 0)]
+        warm_start_from_greedy: bool,
+
+        /// Format of the result printed at the end
+        #[arg(long, value_enum, display_order = 0, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
     /// Benchmark the solution process. Should use "None" or "Greedy" for the remainder function.
     Benchmark {
@@ -246,6 +311,43 @@ pub(crate) enum Commands {
         #[arg(short, long, display_order = 0)]
         iterations: usize,
     },
+    /// Compare two solved networks, or the baseline of a network with its solution.
+    Compare {
+        /// Path to a file containing a solved network.
+        first: String,
+
+        /// Path to a file containing another solution of the same network. If omitted, the baseline of the first network is compared with its solution.
+        second: Option<String>,
+
+        /// Save the comparison as JSON to the given file
+        #[arg(long, display_order = 0)]
+        json: Option<String>,
+
+        /// Save the comparison as CSV to the given file, one row per difference
+        #[arg(long, display_order = 1)]
+        csv: Option<String>,
+    },
+    /// Search for solver parameters that perform well on a set of training networks.
+    Tune {
+        /// Path to a directory containing the (JSON) training networks.
+        directory: String,
+
+        /// Path to [o]utput file to save the best configuration in. Can be passed to "--config".
+        #[arg(short, long, display_order = 0)]
+        output: String,
+
+        /// Strategy used to search the space of solver parameters
+        #[arg(long, value_enum, default_value_t = TuneStrategy::SuccessiveHalving, display_order = 1)]
+        strategy: TuneStrategy,
+
+        /// Number of configurations to evaluate
+        #[arg(long, short = 'n', default_value_t = 32, display_order = 2)]
+        budget: usize,
+
+        /// Maximum allowed cost of a solution, in percent above the lower bound of its network
+        #[arg(long, default_value_t = 10.0, display_order = 3)]
+        cost_tolerance: f64,
+    },
     /// Create a completely random network instead of using an input file.
     Random {
         /// Path to [o]utput file to save the network in
@@ -270,13 +372,93 @@ pub(crate) enum Commands {
         #[arg(long, short = 'p', global = true, display_order = 1)]
         penalty_arcs: bool,
 
+        /// Cost of routing a unit of supply over a penalty arc. Defaults to more than any path through the network
+        #[arg(long, global = true, display_order = 1, requires = "penalty_arcs")]
+        penalty_cost: Option<usize>,
+
+        /// Override penalty costs per OD pair. Pass triples of "s,t,cost"
+        #[arg(long, global = true, display_order = 1, requires = "penalty_arcs", value_parser = parse_triplet, num_args=1..)]
+        penalty_costs: Option<Vec<(usize, usize, usize)>>,
+
         /// Number of vertices
         vertices: usize,
 
         #[command(flatten)]
         random: RandomizationArgs,
     },
-    /// Export the network vertices and arcs as a latex figure.
+    /// Create scenarios from the first scenario of a network by failing arcs.
+    Disrupt {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// How to choose the arcs failing in each scenario
+        #[arg(long, value_enum, default_value_t = DisruptionMethod::KRandom, display_order = 0)]
+        method: DisruptionMethod,
+
+        /// Number of scenarios to generate. Ignored for "n-1", which fails every arc once
+        #[arg(long, default_value_t = 10, display_order = 1)]
+        scenarios: usize,
+
+        /// Number of failing arcs per scenario. Ignored for "n-1"
+        #[arg(long, short = 'k', default_value_t = 1, display_order = 2)]
+        failures: usize,
+
+        /// Maximum relative change of each demand value, e.g. 0.1 for ±10%
+        #[arg(long, default_value_t = 0.0, display_order = 3)]
+        perturbation: f64,
+    },
+    /// Create a network from a GTFS feed, with one unit of supply per trip.
+    ImportGtfs {
+        /// Path to a directory or zip file containing the feed.
+        feed: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Only import routes of the given GTFS route types, e.g. 2 for rail. Defaults to all routes
+        #[arg(long, display_order = 0, num_args = 1..)]
+        route_types: Vec<u16>,
+
+        /// Create a scenario for every service day in the first week of the feed, with the trips running on it, instead of a single scenario with all trips
+        #[arg(long, display_order = 1)]
+        per_service: bool,
+    },
+    /// Create a network of the railway tracks in an OpenStreetMap extract.
+    ImportOsm {
+        /// Path to an OpenStreetMap extract, either as XML or PBF.
+        extract: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Capacity each track adds to its arc
+        #[arg(long, default_value_t = 1, display_order = 0)]
+        track_capacity: usize,
+    },
+    /// Create a network from a GraphML file, e.g. one edited in yEd or Gephi.
+    ImportGraphml {
+        /// Path to a GraphML file.
+        file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+    },
+    /// Convert a network between the dense JSON, sparse JSON and binary formats.
+    Convert {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Format to convert the network into. Defaults to sparse JSON, or to dense JSON if the input is sparse
+        #[arg(long, value_enum, display_order = 0)]
+        format: Option<NetworkFormat>,
+    },
+    /// Export the network or its solution as a latex figure.
     Latex {
         /// Path to a file containing a network to be used as input.
         in_file: String,
@@ -295,7 +477,123 @@ pub(crate) enum Commands {
         /// Enable marking of "station" vertices.
         #[arg(long, display_order = 0)]
         mark_stations: bool,
+
+        /// What the figure shows. All but "network" require a solved network
+        #[arg(long, value_enum, display_order = 0, default_value_t = LatexFigure::Network)]
+        figure: LatexFigure,
+
+        /// Also write a booktabs table of the solution metrics to this file
+        #[arg(long, display_order = 0)]
+        table: Option<String>,
     },
+    /// Export the network as GraphML, e.g. for yEd or Gephi.
+    Graphml {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Include the arc loads of every scenario of the solution. Requires a solved network
+        #[arg(long, display_order = 0)]
+        loads: bool,
+    },
+    /// Export the network as a Graphviz DOT graph.
+    Dot {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Include the arc loads of every scenario of the solution. Requires a solved network
+        #[arg(long, display_order = 0)]
+        loads: bool,
+    },
+    /// Export the network and its solution as GeoJSON, e.g. for QGIS.
+    Geojson {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Name of the coordinate reference system of the vertex positions, e.g. "EPSG:3857"
+        #[arg(long, display_order = 0, conflicts_with = "origin")]
+        crs: Option<String>,
+
+        /// Latitude and longitude of the point at which vertex positions, in kilometres, start. Positions are then written as WGS 84 coordinates. Not needed for networks imported from GTFS or OpenStreetMap, which know their origin
+        #[arg(long, display_order = 0, value_parser = parse_coordinates, allow_hyphen_values = true)]
+        origin: Option<(f64, f64)>,
+    },
+    /// Render the network as an SVG image, without needing a display.
+    Svg {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Width of the image in pixels.
+        #[arg(long, display_order = 0, default_value_t = 1000.0)]
+        width: f32,
+
+        /// Shade arcs by their loads in the solution of this scenario
+        #[arg(long, display_order = 0, conflicts_with = "consistent_flows")]
+        scenario: Option<usize>,
+
+        /// Shade fixed arcs by the consistent flows of the solution
+        #[arg(long, display_order = 0)]
+        consistent_flows: bool,
+    },
+    /// Export a scenario as a DIMACS min-cost flow instance, e.g. for LEMON or CS2.
+    Dimacs {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the output to.
+        out_file: String,
+
+        /// Scenario to export
+        #[arg(long, default_value_t = 0, display_order = 0)]
+        scenario: usize,
+
+        /// Export each OD pair as a commodity of its own, instead of aggregated supply and demand
+        #[arg(long, display_order = 0)]
+        multi_commodity: bool,
+    },
+    /// Read DIMACS flow solutions of the scenarios of a network and validate them.
+    ImportDimacs {
+        /// Path to a file containing a network to be used as input.
+        in_file: String,
+
+        /// Where to save the network along with its solution to.
+        out_file: String,
+
+        /// DIMACS flow solutions, one per scenario in order
+        #[arg(required = true)]
+        solutions: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+#[clap(rename_all = "kebab-case")]
+pub(crate) enum OutputFormat {
+    /// Human-readable summary of the network and its solution
+    Text,
+    /// Status, options, metrics, scenarios and consistent flows as JSON
+    Json,
+    /// One row per arc, with its loads and consistent flow
+    Csv,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+#[clap(rename_all = "kebab-case")]
+pub(crate) enum TuneStrategy {
+    /// Evaluate every sampled configuration on all training networks
+    RandomSearch,
+    /// Evaluate sampled configurations on growing subsets of the training networks, discarding the worse half after each round
+    SuccessiveHalving,
 }
 
 fn parse_triplet(s: &str) -> Result<(usize, usize, usize), String> {
@@ -328,3 +626,17 @@ fn parse_tuple(s: &str) -> Result<(usize, usize), String> {
         .map_err(|_| "Failed to parse second number")?;
     Ok((first, second))
 }
+
+fn parse_coordinates(s: &str) -> Result<(f64, f64), String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 2 {
+        return Err("Coordinates must contain exactly two values".into());
+    }
+    let latitude = parts[0]
+        .parse::<f64>()
+        .map_err(|_| "Failed to parse latitude")?;
+    let longitude = parts[1]
+        .parse::<f64>()
+        .map_err(|_| "Failed to parse longitude")?;
+    Ok((latitude, longitude))
+}
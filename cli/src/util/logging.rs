@@ -29,3 +29,16 @@ macro_rules! attempt {
         }
     };
 }
+
+/// Like `attempt!`, but prints the result of the network in the requested format first, so that
+/// scripts reading it learn why solving failed.
+#[macro_export]
+macro_rules! attempt_solve {
+    ($network:expr, $format:expr, $e:expr) => {
+        if let Err(err) = $e {
+            log::error!("{}", err);
+            print_failure(&$network, $format, &err);
+            std::process::exit(1);
+        }
+    };
+}
@@ -1,7 +1,9 @@
 mod args;
 mod benchmark;
 mod logging;
+mod tune;
 
-pub(super) use args::{Args, Commands};
+pub(super) use args::{Args, Commands, OutputFormat, TuneStrategy};
 pub(super) use benchmark::run_benchmark;
 pub(super) use logging::setup_logger;
+pub(super) use tune::{load_training_networks, run_tuning};
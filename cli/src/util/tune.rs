@@ -0,0 +1,297 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use robmcf_greedy::{
+    CostFunction, DeltaFunction, Network, Options, RelativeDrawFunction, RemainderSolveMethod,
+    Result,
+};
+
+use super::TuneStrategy;
+
+pub(crate) fn load_training_networks(options: &Options, directory: &str) -> Result<Vec<Network>> {
+    let mut files = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "json" || e == "rmcf"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut networks = Vec::new();
+    for file in files {
+        let network = Network::from_file(options, &file.to_string_lossy())?;
+        network.validate_network()?;
+        networks.push(network);
+    }
+    Ok(networks)
+}
+
+pub(crate) fn run_tuning(
+    networks: &[Network],
+    options: &Options,
+    strategy: &TuneStrategy,
+    budget: usize,
+    cost_tolerance: f64,
+) -> Option<(Options, f64)> {
+    // The lower bound does not depend on the parameters being tuned, so it only has to be
+    // calculated once per network.
+    let mut training_set = Vec::new();
+    for (i, network) in networks.iter().enumerate() {
+        let mut network = network.clone();
+        match network.lower_bound() {
+            Ok(()) => training_set.push(network),
+            Err(e) => log::warn!("Skipping training network {}: {e}", i + 1),
+        }
+    }
+    if training_set.is_empty() {
+        return None;
+    }
+    training_set.shuffle(&mut rand::thread_rng());
+
+    let candidates = sample_candidates(options, budget);
+    log::info!(
+        "Tuning {} configurations on {} networks.",
+        candidates.len(),
+        training_set.len()
+    );
+
+    let scored = match strategy {
+        TuneStrategy::RandomSearch => score_all(&candidates, &training_set, cost_tolerance),
+        TuneStrategy::SuccessiveHalving => {
+            successive_halving(candidates, &training_set, cost_tolerance)
+        }
+    };
+    scored.into_iter().next()
+}
+
+/// Samples up to `budget` distinct configurations of the parameters which affect the greedy,
+/// keeping all others from `options`. Remainders are only ever solved greedily, if at all. The
+/// greedy ignores the slack budgets, so they are not tuned either.
+fn sample_candidates(options: &Options, budget: usize) -> Vec<Options> {
+    let remainder_solve_methods = [RemainderSolveMethod::None, RemainderSolveMethod::Greedy];
+    let mut candidates = Vec::new();
+    for cost_fn in CostFunction::value_variants() {
+        for delta_fn in DeltaFunction::value_variants() {
+            for relative_draw_fn in RelativeDrawFunction::value_variants() {
+                for remainder_solve_method in remainder_solve_methods.iter() {
+                    candidates.push(Options {
+                        cost_fn: cost_fn.clone(),
+                        delta_fn: delta_fn.clone(),
+                        relative_draw_fn: relative_draw_fn.clone(),
+                        remainder_solve_method: remainder_solve_method.clone(),
+                        ..options.clone()
+                    });
+                }
+            }
+        }
+    }
+    candidates.shuffle(&mut rand::thread_rng());
+    candidates.truncate(budget);
+    candidates
+}
+
+fn successive_halving(
+    mut candidates: Vec<Options>,
+    training_set: &[Network],
+    cost_tolerance: f64,
+) -> Vec<(Options, f64)> {
+    let subset_sizes = halving_subset_sizes(candidates.len(), training_set.len());
+    let mut scored = Vec::new();
+    for (round, &subset_size) in subset_sizes.iter().enumerate() {
+        // A single remaining candidate only has to be evaluated on all networks
+        if candidates.len() <= 1 && round + 1 < subset_sizes.len() {
+            continue;
+        }
+        scored = score_all(&candidates, &training_set[..subset_size], cost_tolerance);
+        log::info!(
+            "Round {}: {} configurations evaluated on {} networks.",
+            round + 1,
+            candidates.len(),
+            subset_size
+        );
+        candidates = scored
+            .iter()
+            .take(scored.len().div_ceil(2))
+            .map(|(candidate, _)| candidate.clone())
+            .collect();
+    }
+    scored
+}
+
+/// Number of training networks evaluated in each round of successive halving. Early rounds only
+/// look at a fraction of the training set, which doubles every round until the final round
+/// looks at all of it.
+fn halving_subset_sizes(num_candidates: usize, num_networks: usize) -> Vec<usize> {
+    let rounds = (num_candidates.max(1) as f64).log2().ceil() as i32;
+    (0..=rounds)
+        .map(|round| {
+            ((num_networks as f64) / 2f64.powi(rounds - round))
+                .ceil()
+                .max(1.0) as usize
+        })
+        .collect()
+}
+
+fn score_all(
+    candidates: &[Options],
+    training_set: &[Network],
+    cost_tolerance: f64,
+) -> Vec<(Options, f64)> {
+    let mut scored = candidates
+        .iter()
+        .filter_map(|candidate| {
+            score(candidate, training_set, cost_tolerance).map(|score| (candidate.clone(), score))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    scored
+}
+
+/// Mean robustness coefficient over the given networks, or `None` if the candidate fails to
+/// route all supply of any of them with a cost of at most `cost_tolerance` percent above their
+/// lower bound.
+/// Both costs are measured by the candidate's cost function.
+fn score(candidate: &Options, training_set: &[Network], cost_tolerance: f64) -> Option<f64> {
+    let mut total = 0.0;
+    for network in training_set {
+        let mut network = network.clone();
+        network.options = candidate.clone();
+        if let Err(e) = solve(&mut network) {
+            log::debug!("Discarding {:?}: {e}", candidate);
+            return None;
+        }
+        // Unrouted supply does not fail validation, but makes any solution look cheap
+        if network
+            .solutions
+            .iter()
+            .flatten()
+            .any(|solution| solution.supply_remaining.sum() > 0)
+        {
+            log::debug!("Discarding {:?}: supply remains unrouted.", candidate);
+            return None;
+        }
+
+        let lower_bound = network.baseline_cost().ok()?;
+        let cost_limit = (lower_bound as f64 * (1.0 + cost_tolerance / 100.0)) as usize;
+        let cost = network.cost().ok()?;
+        if cost > cost_limit {
+            log::debug!(
+                "Discarding {:?}: cost {cost} exceeds {cost_limit}.",
+                candidate
+            );
+            return None;
+        }
+        total += network.robustness_coefficient().ok()?;
+    }
+    Some(total / training_set.len() as f64)
+}
+
+fn solve(network: &mut Network) -> Result<()> {
+    network.preprocess()?;
+    network.solve()?;
+    network.solve_remainder()?;
+    network.validate_solution()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halving_subset_sizes() {
+        assert_eq!(vec![1, 2, 4, 8], halving_subset_sizes(8, 8));
+        assert_eq!(vec![1, 1, 2, 3], halving_subset_sizes(5, 3));
+        assert_eq!(vec![4], halving_subset_sizes(1, 4));
+    }
+
+    #[test]
+    fn test_sample_candidates() {
+        let candidates = sample_candidates(&Options::default(), usize::MAX);
+        let space = CostFunction::value_variants().len()
+            * DeltaFunction::value_variants().len()
+            * RelativeDrawFunction::value_variants().len()
+            * 2;
+        assert_eq!(space, candidates.len());
+        assert!(candidates.iter().all(|candidate| matches!(
+            candidate.remainder_solve_method,
+            RemainderSolveMethod::None | RemainderSolveMethod::Greedy
+        )));
+        assert_eq!(10, sample_candidates(&Options::default(), 10).len());
+    }
+
+    /// A single path from a to c over the fixed arc (a, b), with its loads as the lower bound.
+    /// Supply from b to c cannot use the fixed arc, and is only routed by greedy remainders.
+    fn training_network(name: &str) -> Network {
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_tune_{name}_{}.json", std::process::id()));
+        std::fs::write(
+            &filename,
+            r#"{
+                "vertices": [
+                    { "name": "a", "x": 0.0, "y": 0.0 },
+                    { "name": "b", "x": 1.0, "y": 0.0 },
+                    { "name": "c", "x": 2.0, "y": 0.0 }
+                ],
+                "capacities": [[0, 2, 0], [0, 0, 2], [0, 0, 0]],
+                "costs": [[0, 1, 0], [0, 0, 1], [0, 0, 0]],
+                "balances": [[[0, 0, 1], [0, 0, 1], [0, 0, 0]], [[0, 0, 2], [0, 0, 0], [0, 0, 0]]],
+                "fixed_arcs": [[0, 1]],
+                "baseline": [
+                    {
+                        "id": 0,
+                        "slack": 0,
+                        "supply_remaining": [[0, 0, 0], [0, 0, 0], [0, 0, 0]],
+                        "arc_loads": [[0, 1, 0], [0, 0, 2], [0, 0, 0]]
+                    },
+                    {
+                        "id": 1,
+                        "slack": 0,
+                        "supply_remaining": [[0, 0, 0], [0, 0, 0], [0, 0, 0]],
+                        "arc_loads": [[0, 2, 0], [0, 0, 2], [0, 0, 0]]
+                    }
+                ],
+                "solutions": null
+            }"#,
+        )
+        .unwrap();
+        let network = Network::from_file(&Options::default(), &filename.to_string_lossy());
+        std::fs::remove_file(&filename).unwrap();
+        network.unwrap()
+    }
+
+    #[test]
+    fn test_score() {
+        let skipping = Options::default();
+        let greedy = Options {
+            remainder_solve_method: RemainderSolveMethod::Greedy,
+            ..Options::default()
+        };
+        let training_set = [training_network("score")];
+
+        assert!(score(&greedy, &training_set, 0.0).is_some());
+        assert!(score(&greedy, &training_set, -50.0).is_none());
+        // leaving the supply from b to c unrouted is cheaper, but not a solution
+        assert!(score(&skipping, &training_set, 0.0).is_none());
+        assert_eq!(1, score_all(&[skipping, greedy], &training_set, 0.0).len());
+    }
+
+    #[test]
+    #[ignore = "the lower bound requires a Gurobi licence"]
+    fn test_run_tuning() {
+        let mut network = training_network("run_tuning");
+        network.baseline = None;
+
+        let (options, score) = run_tuning(
+            &[network],
+            &Options::default(),
+            &TuneStrategy::SuccessiveHalving,
+            8,
+            0.0,
+        )
+        .unwrap();
+        assert!(matches!(
+            options.remainder_solve_method,
+            RemainderSolveMethod::None | RemainderSolveMethod::Greedy
+        ));
+        assert!(score.is_finite());
+    }
+}
@@ -4,7 +4,7 @@ use bevy_mod_picking::prelude::{Listener, On, Pickable, PointerButton};
 use bevy_mod_picking::PickableBundle;
 use bevy_prototype_lyon::prelude::*;
 use rand::Rng;
-use robmcf_greedy::Matrix;
+use robmcf_greedy::{ArcGeometry, Matrix};
 
 use crate::network::vertex::*;
 use crate::{shared::*, NetworkWrapper};
@@ -19,6 +19,7 @@ pub struct Arc {
     pub cost: usize,
     pub load: usize,
     pub fixed: bool,
+    pub bidirectional: bool,
 
     pub color: Color,
     pub layer: f32,
@@ -31,18 +32,24 @@ pub struct Arrow;
 #[derive(Component)]
 pub struct PartialArc;
 
+type LineBundle = (ShapeBundle, Stroke, Arc, PickableBundle, On<Pointer<Click>>);
+type ArrowBundle = (ShapeBundle, Stroke, Fill, Arrow, PickableBundle);
+
 impl Arc {
+    fn geometry(&self) -> ArcGeometry {
+        ArcGeometry {
+            s_pos: self.s_pos.into(),
+            t_pos: self.t_pos.into(),
+            bidirectional: self.bidirectional,
+        }
+    }
+
     pub fn arc_point(&self) -> Vec2 {
-        let midpoint = self.s_pos.midpoint(self.t_pos);
-        let ortho_offshoot = Vec2::new(0., -1.).rotate(self.s_pos - self.t_pos) * 0.2;
-        midpoint + ortho_offshoot
+        self.geometry().arc_point().into()
     }
 
     pub fn line_width(capacity: usize, max: f32) -> f32 {
-        let minimum_width = 3.;
-        let scaling_factor = 25.;
-        let fraction = (capacity as f32) / max;
-        minimum_width + scaling_factor * (0.5 * (4. * fraction - 2.).tanh() + 0.5)
+        ArcGeometry::line_width(capacity, max)
     }
 
     pub fn get_path(&self) -> Path {
@@ -53,23 +60,24 @@ impl Arc {
     }
 
     pub fn get_arrow_translation(&self) -> Vec2 {
-        self.s_pos.midpoint(self.t_pos) + Vec2::new(0., -1.).rotate(self.s_pos - self.t_pos) * 0.1
+        self.geometry().arrow_position().into()
+    }
+
+    fn reversed(&self) -> Self {
+        Arc {
+            s: self.t,
+            t: self.s,
+            s_pos: self.t_pos,
+            t_pos: self.s_pos,
+            ..*self
+        }
     }
 
     pub fn get_arrow_rotation(&self) -> Quat {
-        Quat::from_axis_angle(
-            Vec3::new(0., 0., 1.),
-            Vec2::new(0., -1.).angle_between(self.s_pos - self.t_pos),
-        )
+        Quat::from_axis_angle(Vec3::new(0., 0., 1.), self.geometry().arrow_angle())
     }
 
-    #[allow(clippy::complexity)]
-    pub fn spawn_arc(
-        &self,
-    ) -> (
-        (ShapeBundle, Stroke, Arc, PickableBundle, On<Pointer<Click>>),
-        (ShapeBundle, Stroke, Fill, Arrow, PickableBundle),
-    ) {
+    pub fn spawn_arc(&self) -> (LineBundle, Vec<ArrowBundle>) {
         let mut rng = rand::thread_rng();
         let rand_offset = rng.gen_range(0.01..0.5);
 
@@ -90,13 +98,27 @@ impl Arc {
             On::<Pointer<Click>>::run(toggle_arc_fixed),
         );
 
+        // Single tracks get an arrowhead for each direction
+        let arrows = if self.bidirectional {
+            vec![
+                self.spawn_arrow(rand_offset),
+                self.reversed().spawn_arrow(rand_offset),
+            ]
+        } else {
+            vec![self.spawn_arrow(rand_offset)]
+        };
+
+        (line, arrows)
+    }
+
+    fn spawn_arrow(&self, rand_offset: f32) -> ArrowBundle {
         let shape = shapes::RegularPolygon {
             sides: 3,
             feature: shapes::RegularPolygonFeature::Radius(self.line_width.max(6.)),
             ..shapes::RegularPolygon::default()
         };
 
-        let arrow = (
+        (
             ShapeBundle {
                 path: GeometryBuilder::build_as(&shape),
                 spatial: SpatialBundle {
@@ -121,9 +143,7 @@ impl Arc {
                 },
                 ..default()
             },
-        );
-
-        (line, arrow)
+        )
     }
 }
 
@@ -148,15 +168,35 @@ pub fn spawn_arcs(
     };
     for (s, this_vertex) in network.n.vertices.iter().enumerate() {
         for (t, other_vertex) in network.n.vertices.iter().enumerate() {
-            let capacity = capacities[s][t];
             let is_fixed = network.n.fixed_arcs.contains(&(s, t));
+            let track = network.n.arc_groups.iter().find(|group| {
+                group.is_bidirectional()
+                    && group.contains(s, t)
+                    && !group
+                        .arcs
+                        .iter()
+                        .any(|arc| network.n.fixed_arcs.contains(arc))
+            });
+            // Both directions of a track share one line, drawn when visiting its first arc
+            if track.is_some_and(|track| track.arcs[0] != (s, t)) {
+                continue;
+            }
+            let capacity = match network.n.arc_groups.iter().find(|g| g.contains(s, t)) {
+                Some(group) => capacities[s][t].min(group.capacity),
+                None => capacities[s][t],
+            };
+            let flow = match track {
+                Some(_) if flow_max > 0 => *flows.get(s, t) + *flows.get(t, s),
+                _ if flow_max > 0 => *flows.get(s, t),
+                _ => 0,
+            };
             if capacity > 0 {
                 let (color, layer) = if is_fixed {
                     (app_settings.highlight_color, app_settings.arc_fixed_layer)
                 } else {
                     match &network.n.baseline {
                         Some(_baseline) => {
-                            let scaled = 0.9 * (*flows.get(s, t) as f32 / flow_max as f32);
+                            let scaled = 0.9 * (flow as f32 / flow_max as f32);
                             let mut color = app_settings.baseline_color.to_owned();
                             color.set_alpha(0.1 + scaled);
                             (color, app_settings.arc_fixed_layer)
@@ -169,20 +209,23 @@ pub fn spawn_arcs(
                     t,
                     s_pos: Vec2::new(this_vertex.x, this_vertex.y),
                     t_pos: Vec2::new(other_vertex.x, other_vertex.y),
-                    capacity: capacities[s][t],
+                    capacity,
                     cost: costs[s][t],
                     load: 0,
                     fixed: is_fixed,
+                    bidirectional: track.is_some(),
 
                     color,
                     layer,
                     line_width: Arc::line_width(capacity, cap_max),
                 };
 
-                let (line, arrow) = arc.spawn_arc();
+                let (line, arrows) = arc.spawn_arc();
                 let line_entity = commands.spawn(line).id();
                 commands.entity(line_entity).with_children(|parent| {
-                    parent.spawn(arrow);
+                    for arrow in arrows {
+                        parent.spawn(arrow);
+                    }
                 });
             }
         }
@@ -216,19 +259,22 @@ pub fn begin_arc_creation(
         cost: 0,
         load: 0,
         fixed: is_fixed,
+        bidirectional: false,
 
         color,
         layer,
         line_width: Arc::line_width(1, network.n.capacities.max() as f32),
     };
 
-    let (line, arrow) = arc.spawn_arc();
+    let (line, arrows) = arc.spawn_arc();
     let line_entity = commands.spawn(line).id();
     commands
         .entity(line_entity)
         .insert(PartialArc)
         .with_children(|parent| {
-            parent.spawn(arrow);
+            for arrow in arrows {
+                parent.spawn(arrow);
+            }
         });
 }
 
@@ -177,6 +177,7 @@ pub fn create_vertex(
         x: world_coords.0.x,
         y: world_coords.0.y,
         is_station: true,
+        capacity: None,
     };
     Vertex::spawn(network.num_vertices, &vertex, &mut commands, &app_settings);
     network.num_vertices += 1;
@@ -187,6 +188,9 @@ pub fn create_vertex(
     network.n.vertices.push(vertex);
     network.n.capacities.extend(&row, &column);
     network.n.costs.extend(&row, &column);
+    for metric in network.n.cost_metrics.values_mut() {
+        metric.extend(&row, &column);
+    }
     for balance in &mut network.n.balances {
         balance.extend(&row, &column);
     }
@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+
+use crate::ArcGroup;
+
+/// Flow of an OD pair as its origin, destination and triples of "u, v, load".
+type FractionalFlow = (usize, usize, Vec<(usize, usize, f64)>);
+/// Arc loads of a flow as triples of "u, v, load".
+type RoundedFlow = Vec<(usize, usize, usize)>;
+
+/// Decomposes the flow from `s` to `t`, given as triples of "u, v, load", into paths along with
+/// the amount of flow they carry. Cycles are cancelled along the way and do not show up in any
+/// path. Flow which cannot be attributed to a path, because it is not conserved, is ignored.
+pub(crate) fn decompose_flow(
+    arc_loads: &[(usize, usize, usize)],
+    s: usize,
+    t: usize,
+) -> Vec<(Vec<usize>, usize)> {
+    let mut remaining = arc_loads
+        .iter()
+        .filter(|(_, _, load)| *load > 0)
+        .map(|&(u, v, load)| ((u, v), load))
+        .collect::<BTreeMap<_, _>>();
+    let mut paths = Vec::new();
+
+    'paths: loop {
+        let mut path = vec![s];
+        while path[path.len() - 1] != t {
+            let u = path[path.len() - 1];
+            let Some(v) = remaining
+                .range((u, 0)..=(u, usize::MAX))
+                .map(|((_, v), _)| *v)
+                .next()
+            else {
+                break 'paths;
+            };
+
+            match path.iter().position(|w| *w == v) {
+                Some(position) => {
+                    let mut cycle = path.split_off(position);
+                    cycle.push(v);
+                    subtract(&mut remaining, &cycle);
+                    path.push(v);
+                }
+                None => path.push(v),
+            }
+        }
+        let amount = subtract(&mut remaining, &path);
+        paths.push((path, amount));
+    }
+
+    paths
+}
+
+/// Rounds the fractional flows of several OD pairs, given as their origin, destination and
+/// triples of "u, v, load", to whole units. Rounding every arc on its own would break flow
+/// conservation, so each flow is decomposed into paths instead, whose amounts are rounded such
+/// that they keep adding up to the rounded total of their flow.
+///
+/// The flows are rounded jointly, so that their loads together stay within the capacities of
+/// `limits`, which may stand for single arcs, arc groups or the arcs into a vertex. Paths are
+/// rounded up in the order of their remainders, skipping those which no longer fit. Only if no
+/// path of a flow fits anymore is a limit exceeded, which is logged.
+pub(crate) fn round_flows(flows: &[FractionalFlow], limits: &[ArcGroup]) -> Vec<RoundedFlow> {
+    // decomposed in millionths of a unit, below which Gurobi's tolerances lie anyway
+    let scale = 1e6;
+    let mut paths = Vec::new();
+    let mut totals = Vec::new();
+    for (i, (s, t, arc_loads)) in flows.iter().enumerate() {
+        let scaled = arc_loads
+            .iter()
+            .map(|&(u, v, load)| (u, v, (load.max(0.0) * scale).round() as usize))
+            .collect::<Vec<_>>();
+        let mut total = 0.0;
+        for (path, amount) in decompose_flow(&scaled, *s, *t) {
+            let amount = amount as f64 / scale;
+            total += amount;
+            paths.push((i, path, amount));
+        }
+        totals.push(total.round() as usize);
+    }
+
+    // limits each path counts towards, once for every arc of the path they contain
+    let mut arc_limits: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (l, limit) in limits.iter().enumerate() {
+        for arc in limit.arcs.iter() {
+            arc_limits.entry(*arc).or_default().push(l);
+        }
+    }
+    let path_limits = paths
+        .iter()
+        .map(|(_, path, _)| {
+            path.windows(2)
+                .flat_map(|arc| arc_limits.get(&(arc[0], arc[1])).into_iter().flatten())
+                .copied()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // rounding down never exceeds a limit which the fractional flows respect
+    let mut rounded = paths
+        .iter()
+        .map(|(_, _, amount)| amount.floor() as usize)
+        .collect::<Vec<_>>();
+    let mut used = vec![0; limits.len()];
+    for (p, amount) in rounded.iter().enumerate() {
+        for &l in path_limits[p].iter() {
+            used[l] += amount;
+        }
+    }
+    let mut missing = totals;
+    for ((i, _, _), amount) in paths.iter().zip(rounded.iter()) {
+        missing[*i] = missing[*i].saturating_sub(*amount);
+    }
+
+    // largest remainder method, across all flows
+    let remainder = |p: usize| paths[p].2 - paths[p].2.floor();
+    let mut by_remainder = (0..paths.len()).collect::<Vec<_>>();
+    by_remainder.sort_by(|&p, &q| remainder(q).total_cmp(&remainder(p)));
+    for &p in by_remainder.iter() {
+        let i = paths[p].0;
+        let fits = path_limits[p].iter().all(|&l| {
+            let uses = path_limits[p].iter().filter(|&&k| k == l).count();
+            used[l] + uses <= limits[l].capacity
+        });
+        if missing[i] == 0 || !fits {
+            continue;
+        }
+        rounded[p] += 1;
+        missing[i] -= 1;
+        for &l in path_limits[p].iter() {
+            used[l] += 1;
+        }
+    }
+    for &p in by_remainder.iter() {
+        let i = paths[p].0;
+        if missing[i] == 0 {
+            continue;
+        }
+        log::warn!(
+            "Rounding the flow from {} to {} exceeds a capacity, as none of its paths has room left.",
+            flows[i].0,
+            flows[i].1
+        );
+        rounded[p] += 1;
+        missing[i] -= 1;
+    }
+
+    let mut loads = vec![BTreeMap::new(); flows.len()];
+    for ((i, path, _), amount) in paths.iter().zip(rounded).filter(|(_, amount)| *amount > 0) {
+        for arc in path.windows(2) {
+            *loads[*i].entry((arc[0], arc[1])).or_insert(0) += amount;
+        }
+    }
+    loads
+        .into_iter()
+        .map(|loads| {
+            loads
+                .into_iter()
+                .map(|((u, v), load)| (u, v, load))
+                .collect()
+        })
+        .collect()
+}
+
+/// Subtracts the minimum load along `path` from all of its arcs, and returns that minimum.
+fn subtract(remaining: &mut BTreeMap<(usize, usize), usize>, path: &[usize]) -> usize {
+    let amount = path
+        .windows(2)
+        .map(|arc| remaining[&(arc[0], arc[1])])
+        .min()
+        .unwrap_or(0);
+    for arc in path.windows(2) {
+        let load = remaining.get_mut(&(arc[0], arc[1])).unwrap();
+        *load -= amount;
+        if *load == 0 {
+            remaining.remove(&(arc[0], arc[1]));
+        }
+    }
+    amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompose_flow() {
+        // 0 -> 1 -> 3 carries 2 units, 0 -> 2 -> 3 carries 1 unit
+        let arc_loads = [(0, 1, 2), (1, 3, 2), (0, 2, 1), (2, 3, 1)];
+
+        assert_eq!(
+            vec![(vec![0, 1, 3], 2), (vec![0, 2, 3], 1)],
+            decompose_flow(&arc_loads, 0, 3)
+        );
+    }
+
+    #[test]
+    fn test_decompose_flow_cancels_cycles() {
+        // 0 -> 1 -> 2 -> 3, with a cycle 1 -> 2 -> 1
+        let arc_loads = [(0, 1, 1), (1, 2, 2), (2, 1, 1), (2, 3, 1)];
+
+        assert_eq!(
+            vec![(vec![0, 1, 2, 3], 1)],
+            decompose_flow(&arc_loads, 0, 3)
+        );
+    }
+
+    #[test]
+    fn test_round_flows_conserves_flow() {
+        // 2 units from 0 to 3, split in thirds over three paths
+        let third = 2.0 / 3.0;
+        let arc_loads = vec![
+            (0, 1, third),
+            (1, 3, third),
+            (0, 2, third),
+            (2, 3, third),
+            (0, 3, third),
+        ];
+        let rounded = round_flows(&[(0, 3, arc_loads)], &[]).remove(0);
+
+        assert_eq!(2, decompose_flow(&rounded, 0, 3).len());
+        assert_eq!(
+            2,
+            rounded
+                .iter()
+                .filter(|(u, _, _)| *u == 0)
+                .map(|(_, _, load)| load)
+                .sum::<usize>()
+        );
+        assert!(rounded.iter().all(|(_, _, load)| *load == 1));
+    }
+
+    #[test]
+    fn test_round_flows_respects_shared_capacity() {
+        // one unit each from 0 to 3 and from 1 to 3, both sending half of it over arc (1,3),
+        // which fits exactly one unit, and a quarter over each of two detours
+        let flows = [
+            (
+                0,
+                3,
+                vec![
+                    (0, 1, 0.5),
+                    (1, 3, 0.5),
+                    (0, 2, 0.25),
+                    (2, 3, 0.25),
+                    (0, 4, 0.25),
+                    (4, 3, 0.25),
+                ],
+            ),
+            (
+                1,
+                3,
+                vec![
+                    (1, 3, 0.5),
+                    (1, 2, 0.25),
+                    (2, 3, 0.25),
+                    (1, 4, 0.25),
+                    (4, 3, 0.25),
+                ],
+            ),
+        ];
+        let limits = [ArcGroup {
+            arcs: vec![(1, 3)],
+            capacity: 1,
+        }];
+        let rounded = round_flows(&flows, &limits);
+
+        let load = |arc_loads: &[(usize, usize, usize)], u: usize, v: usize| {
+            arc_loads
+                .iter()
+                .find(|(a_0, a_1, _)| (*a_0, *a_1) == (u, v))
+                .map_or(0, |(_, _, load)| *load)
+        };
+        assert_eq!(
+            1,
+            rounded
+                .iter()
+                .map(|flow| load(flow.as_slice(), 1, 3))
+                .sum::<usize>()
+        );
+        for (flow, (s, t, _)) in rounded.iter().zip(flows.iter()) {
+            let paths = decompose_flow(flow, *s, *t);
+            assert_eq!(1, paths.iter().map(|(_, amount)| amount).sum::<usize>());
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use rayon::{iter::ParallelIterator, ThreadPoolBuilder};
 
 use crate::{
-    network::{AuxiliaryNetwork, ScenarioSolution},
-    Options, Result,
+    network::{record_commodity_flow, record_path, AuxiliaryNetwork, ScenarioSolution},
+    Matrix, Options, Result,
 };
 
 pub(crate) fn greedy(
@@ -33,9 +33,22 @@ pub(crate) fn greedy(
                         );
 
                         scenario.network_state.use_arc(token, next_vertex);
+                        record_commodity_flow(
+                            &mut scenario.commodity_flows,
+                            (token.origin, token.t),
+                            (token.s, next_vertex),
+                            1,
+                        );
                         token.s = next_vertex;
+                        token.path.push(next_vertex);
 
                         if token.s == token.t {
+                            record_path(
+                                &mut scenario.paths,
+                                (token.origin, token.t),
+                                &token.path,
+                                1,
+                            );
                             scenario.supply_remaining.decrement(token.origin, token.t);
                             scenario.supply_tokens.remove(i);
                             continue;
@@ -84,6 +97,13 @@ fn get_solutions(network: &AuxiliaryNetwork) -> Result<Vec<ScenarioSolution>> {
                 slack,
                 supply_remaining: scenario.supply_remaining.clone(),
                 arc_loads: scenario.network_state.arc_loads.clone(),
+                unmet_demand: Matrix::filled_with(
+                    0,
+                    scenario.supply_remaining.num_rows(),
+                    scenario.supply_remaining.num_columns(),
+                ),
+                commodity_flows: scenario.commodity_flows.clone(),
+                paths: scenario.paths.clone(),
             })
         })
         .collect::<Result<Vec<_>>>()
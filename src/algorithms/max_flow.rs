@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+use crate::Matrix;
+
+/// Edmonds-Karp maximum flow from `s` to `t`, stopping early once `limit` is reached.
+///
+/// Returns the flow value and the vertices reachable from `s` in the final residual network. If
+/// the flow is below `limit`, these form the source side of a minimum cut.
+pub(crate) fn max_flow(
+    capacities: &Matrix<usize>,
+    s: usize,
+    t: usize,
+    limit: usize,
+) -> (usize, Vec<bool>) {
+    let mut residual = capacities.clone();
+    let mut flow = 0;
+
+    while flow < limit {
+        let predecessors = breadth_first_search(&residual, s);
+        if predecessors[t].is_none() {
+            break;
+        }
+
+        let mut path = vec![];
+        let mut v = t;
+        while v != s {
+            let u = predecessors[v].unwrap_or(s);
+            path.push((u, v));
+            v = u;
+        }
+
+        let bottleneck = path
+            .iter()
+            .map(|(u, v)| *residual.get(*u, *v))
+            .min()
+            .unwrap_or(0)
+            .min(limit - flow);
+        for (u, v) in path {
+            residual.set(u, v, residual.get(u, v) - bottleneck);
+            residual.set(v, u, residual.get(v, u).saturating_add(bottleneck));
+        }
+        flow += bottleneck;
+    }
+
+    let source_side = breadth_first_search(&residual, s)
+        .iter()
+        .map(|predecessor| predecessor.is_some())
+        .collect();
+    (flow, source_side)
+}
+
+/// The arcs leaving the source side of a cut.
+pub(crate) fn cut_arcs(capacities: &Matrix<usize>, source_side: &[bool]) -> Vec<(usize, usize)> {
+    capacities
+        .indices()
+        .filter(|&(u, v)| source_side[u] && !source_side[v] && *capacities.get(u, v) > 0)
+        .collect()
+}
+
+fn breadth_first_search(residual: &Matrix<usize>, s: usize) -> Vec<Option<usize>> {
+    let mut predecessors = vec![None; residual.num_rows()];
+    predecessors[s] = Some(s);
+    let mut queue = VecDeque::from([s]);
+    while let Some(u) = queue.pop_front() {
+        for (v, predecessor) in predecessors.iter_mut().enumerate() {
+            if predecessor.is_none() && *residual.get(u, v) > 0 {
+                *predecessor = Some(u);
+                queue.push_back(v);
+            }
+        }
+    }
+    predecessors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Matrix<usize> {
+        // 0 -> 1 -> 3 and 0 -> 2 -> 3, where (1, 3) and (2, 3) are the bottleneck
+        Matrix::from_elements(&[0, 5, 5, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0], 4, 4)
+    }
+
+    #[test]
+    fn test_max_flow_value() {
+        let capacities = setup();
+        let (flow, _) = max_flow(&capacities, 0, 3, usize::MAX);
+
+        assert_eq!(3, flow);
+    }
+
+    #[test]
+    fn test_max_flow_limit() {
+        let capacities = setup();
+        let (flow, _) = max_flow(&capacities, 0, 3, 2);
+
+        assert_eq!(2, flow);
+    }
+
+    #[test]
+    fn test_min_cut() {
+        let capacities = setup();
+        let (_, source_side) = max_flow(&capacities, 0, 3, usize::MAX);
+
+        assert_eq!(vec![true, true, true, false], source_side);
+        assert_eq!(vec![(1, 3), (2, 3)], cut_arcs(&capacities, &source_side));
+    }
+}
@@ -1,5 +1,9 @@
+mod flow_decomposition;
 mod floyd_warshall;
 mod greedy;
+mod max_flow;
 
+pub(crate) use flow_decomposition::{decompose_flow, round_flows};
 pub(crate) use floyd_warshall::*;
 pub(crate) use greedy::greedy;
+pub(crate) use max_flow::*;
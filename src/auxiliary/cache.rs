@@ -0,0 +1,124 @@
+//! Generating supply tokens is the expensive part of preprocessing, but only depends on part of
+//! a network and its options. They are cached on disk, keyed by a hash of exactly that part, so
+//! that runs differing in anything else (e.g. the relative draw function) can reuse them.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{ArcGroup, Matrix, Network, Result};
+
+use super::{supply_token::SupplyToken, AuxiliaryNetwork};
+
+/// Incremented whenever the cached data or the way it is generated changes.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    version: u32,
+    capacities: &'a Matrix<usize>,
+    costs: &'a Matrix<usize>,
+    fixed_arcs: &'a [(usize, usize)],
+    delta_fn: String,
+    remainder_solve_method: String,
+    balances: &'a [Matrix<usize>],
+    capacity_overrides: &'a [Vec<(usize, usize, usize)>],
+    arc_groups: &'a [ArcGroup],
+    penalty_arcs: &'a [(usize, usize)],
+}
+
+impl AuxiliaryNetwork {
+    /// Like `from_network`, but loads the supply tokens from the cache in `directory` if
+    /// possible, and stores them there otherwise.
+    pub(crate) fn from_cache(network: &Network, directory: &str) -> Result<Self> {
+        let key = CacheKey {
+            version: CACHE_VERSION,
+            capacities: &network.capacities,
+            costs: network.routing_costs(),
+            fixed_arcs: &network.fixed_arcs,
+            delta_fn: format!("{:?}", network.options.delta_fn),
+            remainder_solve_method: format!("{:?}", network.options.remainder_solve_method),
+            balances: &network.balances,
+            capacity_overrides: &network.capacity_overrides,
+            arc_groups: &network.arc_groups,
+            penalty_arcs: &network.penalty_arcs,
+        };
+        let key = match bincode::serialize(&key) {
+            Ok(key) => fnv1a(&key),
+            Err(e) => {
+                log::warn!("Could not hash the network for the preprocessing cache: {e}");
+                return AuxiliaryNetwork::from_network(network);
+            }
+        };
+        let path = Path::new(directory).join(format!("{key:016x}.bin"));
+
+        if let Ok(bytes) = std::fs::read(&path) {
+            match bincode::deserialize::<Vec<Vec<(usize, SupplyToken)>>>(&bytes) {
+                Ok(supply_tokens) if supply_tokens.len() == network.balances.len() => {
+                    log::info!("Loaded preprocessing results from {}.", path.display());
+                    let supply_tokens = supply_tokens.into_iter().map(expand).collect();
+                    return Ok(AuxiliaryNetwork::from_supply_tokens(network, supply_tokens));
+                }
+                Ok(_) => log::warn!("Ignoring mismatched cache file {}.", path.display()),
+                Err(e) => log::warn!("Ignoring corrupt cache file {}: {e}", path.display()),
+            }
+        }
+
+        let supply_tokens = AuxiliaryNetwork::generate_supply_tokens(network)?;
+        let compressed = supply_tokens
+            .iter()
+            .map(|t| compress(t))
+            .collect::<Vec<_>>();
+        let written = bincode::serialize(&compressed)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                std::fs::create_dir_all(directory)
+                    .and_then(|_| std::fs::write(&path, bytes))
+                    .map_err(|e| e.to_string())
+            });
+        match written {
+            Ok(_) => log::debug!("Cached preprocessing results in {}.", path.display()),
+            Err(e) => log::warn!("Could not cache preprocessing results: {e}"),
+        }
+        Ok(AuxiliaryNetwork::from_supply_tokens(network, supply_tokens))
+    }
+}
+
+/// Tokens of the same OD pair are identical and adjacent, so they are stored once along with
+/// their number.
+fn compress(supply_tokens: &[SupplyToken]) -> Vec<(usize, &SupplyToken)> {
+    let mut compressed: Vec<(usize, &SupplyToken)> = Vec::new();
+    for token in supply_tokens {
+        match compressed.last_mut() {
+            Some((count, last)) if (last.origin, last.t) == (token.origin, token.t) => *count += 1,
+            _ => compressed.push((1, token)),
+        }
+    }
+    compressed
+}
+
+fn expand(compressed: Vec<(usize, SupplyToken)>) -> Vec<SupplyToken> {
+    compressed
+        .into_iter()
+        .flat_map(|(count, token)| std::iter::repeat_n(token, count))
+        .collect()
+}
+
+/// 64-bit FNV-1a hash. Unlike `DefaultHasher`, it is stable across runs and Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(0xcbf29ce484222325, fnv1a(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(b"a"));
+        assert_eq!(0x85944171f73967e8, fnv1a(b"foobar"));
+    }
+}
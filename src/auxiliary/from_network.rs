@@ -10,11 +10,18 @@ use crate::{
     Matrix, Network, Result,
 };
 
+use super::supply_token::SupplyToken;
+
 impl AuxiliaryNetwork {
     pub(crate) fn from_network(network: &Network) -> Result<Self> {
-        let num_vertices = network.vertices.len();
-        let arc_loads = Matrix::filled_with(0, num_vertices, num_vertices);
+        Ok(AuxiliaryNetwork::from_supply_tokens(
+            network,
+            AuxiliaryNetwork::generate_supply_tokens(network)?,
+        ))
+    }
 
+    /// Generates the supply tokens of every scenario, which make up the bulk of preprocessing.
+    pub(super) fn generate_supply_tokens(network: &Network) -> Result<Vec<Vec<SupplyToken>>> {
         let mut capacities = network.capacities.clone();
         for (a_0, a_1) in &network.fixed_arcs {
             capacities.set(*a_0, *a_1, usize::MAX);
@@ -24,51 +31,95 @@ impl AuxiliaryNetwork {
         // we can initially reuse distance and successor maps between all (s, t) pairs and
         // balances, since the arcs for the globally shortest path from s to t is guaranteed to
         // be included in the intermediate arc set of (s, t).
-        let (distance_map, _) = floyd_warshall(&capacities, &network.costs);
+        let (distance_map, _) = floyd_warshall(&capacities, network.routing_costs());
 
         // intermediate arc sets only need to be computed once. Their sole purpose is to act as a
         // mask on capacities when Floyd-Warshall is refreshed in the greedy iterations.
-        let arc_sets = generate_intermediate_arc_sets(
+        let mut arc_sets = generate_intermediate_arc_sets(
             &distance_map,
-            &network.costs,
+            network.routing_costs(),
             &capacities,
             &network.options.delta_fn,
         );
+        network.restrict_penalty_arcs(&mut arc_sets);
 
-        let scenarios: DashMap<usize, Scenario> = DashMap::new();
+        let mut scenario_supply_tokens = Vec::new();
         for (i, balance) in network.balances.iter().enumerate() {
-            let network_state = NetworkState {
-                scenario_id: i,
-                fixed_arcs: network.fixed_arcs.clone(),
-                capacities: capacities.clone(),
-                costs: Arc::new(network.costs.clone()),
-                arc_loads: arc_loads.clone(),
-                relative_draws: HashMap::new(),
+            let scenario_capacities = network.greedy_capacities(i);
+
+            // closed or otherwise changed arcs may remove the shortest path between s and t, in
+            // which case the shared intermediate arc sets could be missing the detours.
+            let overridden;
+            let (scenario_distance_map, scenario_arc_sets) = if network.has_capacity_overrides(i) {
+                let (distance_map, _) =
+                    floyd_warshall(&scenario_capacities, network.routing_costs());
+                let mut arc_sets = generate_intermediate_arc_sets(
+                    &distance_map,
+                    network.routing_costs(),
+                    &scenario_capacities,
+                    &network.options.delta_fn,
+                );
+                network.restrict_penalty_arcs(&mut arc_sets);
+                overridden = (distance_map, arc_sets);
+                (&overridden.0, &overridden.1)
+            } else {
+                (&distance_map, &arc_sets)
             };
 
             let mut supply_tokens = generate_supply_tokens(
                 balance,
                 &network.fixed_arcs,
                 network.options.remainder_solve_method.clone(),
-                &arc_sets,
-                &capacities,
-                &network.costs,
+                scenario_arc_sets,
+                &scenario_capacities,
+                network.routing_costs(),
             )?;
-            supply_tokens.sort_by_key(|token| *distance_map.get(token.s, token.t));
+            supply_tokens.sort_by_key(|token| *scenario_distance_map.get(token.s, token.t));
+            scenario_supply_tokens.push(supply_tokens);
+        }
+        Ok(scenario_supply_tokens)
+    }
+
+    pub(super) fn from_supply_tokens(
+        network: &Network,
+        scenario_supply_tokens: Vec<Vec<SupplyToken>>,
+    ) -> Self {
+        let num_vertices = network.vertices.len();
+        let arc_loads = Matrix::filled_with(0, num_vertices, num_vertices);
+
+        let scenarios: DashMap<usize, Scenario> = DashMap::new();
+        for ((i, balance), supply_tokens) in network
+            .balances
+            .iter()
+            .enumerate()
+            .zip(scenario_supply_tokens)
+        {
+            let network_state = NetworkState {
+                scenario_id: i,
+                fixed_arcs: network.fixed_arcs.clone(),
+                capacities: network.greedy_capacities(i),
+                arc_groups: network.scenario_arc_groups(i, true, None),
+                vertex_capacities: network.residual_vertex_capacities(None),
+                costs: Arc::new(network.routing_costs().clone()),
+                arc_loads: arc_loads.clone(),
+                relative_draws: HashMap::new(),
+            };
 
             let scenario = Scenario {
                 id: i,
                 supply_tokens,
                 supply_remaining: balance.clone(),
                 network_state,
+                commodity_flows: vec![],
+                paths: vec![],
             };
             log::debug!("Generated {}", scenario);
             scenarios.insert(i, scenario);
         }
 
-        Ok(AuxiliaryNetwork {
+        AuxiliaryNetwork {
             scenarios,
             fixed_arcs: network.fixed_arcs.clone(),
-        })
+        }
     }
 }
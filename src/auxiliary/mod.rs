@@ -1,3 +1,4 @@
+mod cache;
 mod from_network;
 mod network_state;
 mod preprocessing;
@@ -2,7 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     algorithms::{floyd_warshall, invert_predecessors},
-    Matrix, Result, SolverError,
+    ArcGroup, Matrix, Result, SolverError,
 };
 
 use super::supply_token::SupplyToken;
@@ -15,6 +15,8 @@ pub(crate) struct NetworkState {
     pub(crate) relative_draws: HashMap<(usize, usize), i64>,
 
     pub(crate) capacities: Matrix<usize>,
+    pub(crate) arc_groups: Vec<ArcGroup>,
+    pub(crate) vertex_capacities: Vec<Option<usize>>,
     pub(crate) costs: Arc<Matrix<usize>>,
 
     pub(crate) arc_loads: Matrix<usize>,
@@ -27,10 +29,14 @@ impl NetworkState {
             self.scenario_id,
             token,
         );
-        let (distance_map, predecessor_map) = floyd_warshall(
-            &self.capacities.apply_mask(&token.intermediate_arc_set, 0),
-            &self.costs,
-        );
+        let mut capacities = self.capacities.apply_mask(&token.intermediate_arc_set, 0);
+        // no arc may lead into a vertex whose throughput is exhausted
+        for v in (0..self.vertex_capacities.len()).filter(|v| self.is_saturated(*v)) {
+            for u in 0..self.vertex_capacities.len() {
+                capacities.set(u, v, 0);
+            }
+        }
+        let (distance_map, predecessor_map) = floyd_warshall(&capacities, &self.costs);
         let successor_map = invert_predecessors(&predecessor_map)?;
 
         token.distances = distance_map;
@@ -38,11 +44,39 @@ impl NetworkState {
         Ok(())
     }
 
+    fn is_saturated(&self, vertex: usize) -> bool {
+        self.vertex_capacities[vertex] == Some(0)
+    }
+
     pub(crate) fn use_arc(&mut self, token: &mut SupplyToken, next_vertex: usize) {
         token.intermediate_arc_set.set(token.s, next_vertex, false);
 
         let _ = self.arc_loads.increment(token.s, next_vertex);
         let remaining_capacity = self.capacities.decrement(token.s, next_vertex);
+
+        // all arcs of a group are limited by whatever capacity the group has left
+        for group in self
+            .arc_groups
+            .iter_mut()
+            .filter(|group| group.contains(token.s, next_vertex))
+        {
+            group.capacity = group.capacity.saturating_sub(1);
+            for (a_0, a_1) in group.arcs.iter() {
+                if *self.capacities.get(*a_0, *a_1) > group.capacity {
+                    self.capacities.set(*a_0, *a_1, group.capacity);
+                }
+            }
+        }
+        if let Some(vertex_capacity) = self.vertex_capacities[next_vertex].as_mut() {
+            *vertex_capacity = vertex_capacity.saturating_sub(1);
+            if *vertex_capacity == 0 {
+                log::info!(
+                    "({}): Vertex {} has reached its capacity.",
+                    self.scenario_id,
+                    next_vertex,
+                );
+            }
+        }
         if remaining_capacity == 0 {
             log::info!(
                 "({}): Arc ({}->{}) has reached its capacity.",
@@ -61,6 +95,15 @@ impl NetworkState {
             if !(*token.intermediate_arc_set.get(fixed_arc.0, fixed_arc.1)) {
                 continue;
             }
+            // fixed arcs whose capacity a scenario overrides can run full
+            if *self.capacities.get(fixed_arc.0, fixed_arc.1) == 0 {
+                continue;
+            }
+            if self.is_saturated(fixed_arc.1)
+                || (token.s != fixed_arc.0 && self.is_saturated(fixed_arc.0))
+            {
+                continue;
+            }
 
             let cost_via_fixed_arc = token
                 .distances
@@ -116,3 +159,77 @@ impl NetworkState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vertices a, b and c, with a single track of capacity 3 between b and c.
+    fn setup() -> NetworkState {
+        NetworkState {
+            scenario_id: 0,
+            fixed_arcs: vec![],
+            relative_draws: HashMap::new(),
+            capacities: Matrix::from_elements(&[0, 2, 0, 0, 0, 2, 0, 2, 0], 3, 3),
+            arc_groups: vec![ArcGroup::bidirectional(1, 2, 3)],
+            vertex_capacities: vec![None; 3],
+            costs: Arc::new(Matrix::from_elements(&[0, 1, 0, 0, 0, 1, 0, 1, 0], 3, 3)),
+            arc_loads: Matrix::filled_with(0, 3, 3),
+        }
+    }
+
+    fn token(s: usize, t: usize) -> SupplyToken {
+        SupplyToken {
+            origin: s,
+            s,
+            t,
+            path: vec![s],
+            intermediate_arc_set: Matrix::filled_with(true, 3, 3),
+            distances: Matrix::filled_with(0, 3, 3),
+            successors: Matrix::filled_with(0, 3, 3),
+        }
+    }
+
+    #[test]
+    fn test_use_arc_in_group() {
+        let mut state = setup();
+        state.use_arc(&mut token(1, 2), 2);
+        assert_eq!(2, state.arc_groups[0].capacity);
+        assert_eq!(1, *state.capacities.get(1, 2));
+        assert_eq!(2, *state.capacities.get(2, 1));
+
+        state.use_arc(&mut token(1, 2), 2);
+        assert_eq!(2, *state.arc_loads.get(1, 2));
+        assert_eq!(0, *state.capacities.get(1, 2));
+        assert_eq!(1, *state.capacities.get(2, 1));
+
+        // the opposite direction takes the last unit of the shared capacity
+        let mut token_c_b = token(2, 1);
+        assert_eq!(1, state.get_next_vertex(&mut token_c_b).unwrap());
+        state.use_arc(&mut token_c_b, 1);
+        assert_eq!(0, state.arc_groups[0].capacity);
+        assert_eq!(0, *state.capacities.get(2, 1));
+        assert!(matches!(
+            state.get_next_vertex(&mut token(2, 1)),
+            Err(SolverError::NoFeasibleFlowError(0))
+        ));
+    }
+
+    #[test]
+    fn test_vertex_capacity() {
+        let mut state = setup();
+        state.vertex_capacities[2] = Some(1);
+        let mut token_b_c = token(1, 2);
+        assert_eq!(2, state.get_next_vertex(&mut token_b_c).unwrap());
+        state.use_arc(&mut token_b_c, 2);
+        assert_eq!(Some(0), state.vertex_capacities[2]);
+
+        // c cannot be entered anymore, although (b,c) has capacity left
+        assert_eq!(1, *state.capacities.get(1, 2));
+        assert!(matches!(
+            state.get_next_vertex(&mut token(1, 2)),
+            Err(SolverError::NoFeasibleFlowError(0))
+        ));
+        assert_eq!(1, state.get_next_vertex(&mut token(2, 1)).unwrap());
+    }
+}
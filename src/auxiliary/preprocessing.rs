@@ -41,6 +41,7 @@ pub(crate) fn generate_supply_tokens(
             origin: s,
             s,
             t,
+            path: vec![s],
             intermediate_arc_set: arc_set.clone(),
             distances: distance_map,
             successors: successor_map,
@@ -1,6 +1,10 @@
 use std::{collections::HashMap, fmt::Display};
 
-use crate::{options::RelativeDrawFunction, Matrix};
+use crate::{
+    network::{CommodityFlow, FlowPath},
+    options::RelativeDrawFunction,
+    Matrix,
+};
 
 use super::{network_state::NetworkState, supply_token::SupplyToken};
 
@@ -10,6 +14,8 @@ pub(crate) struct Scenario {
     pub(crate) supply_tokens: Vec<SupplyToken>,
     pub(crate) supply_remaining: Matrix<usize>,
     pub(crate) network_state: NetworkState,
+    pub(crate) commodity_flows: Vec<CommodityFlow>,
+    pub(crate) paths: Vec<FlowPath>,
 }
 
 impl Scenario {
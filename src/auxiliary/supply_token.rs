@@ -1,10 +1,14 @@
+use serde::{Deserialize, Serialize};
+
 use crate::matrix::Matrix;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub(crate) struct SupplyToken {
     pub(crate) origin: usize,
     pub(crate) s: usize,
     pub(crate) t: usize,
+    /// Vertices visited so far, starting at the origin
+    pub(crate) path: Vec<usize>,
 
     pub(crate) intermediate_arc_set: Matrix<bool>,
     pub(crate) distances: Matrix<usize>,
@@ -0,0 +1,102 @@
+//! Geometry of drawn arcs, shared by the GUI and the SVG renderer. Positions are in the
+//! coordinates of the vertices, with y growing upwards.
+
+type Point = (f32, f32);
+
+/// An arc drawn as a quadratic Bézier curve from `s_pos` to `t_pos`. Single arcs bend to one
+/// side, so that both directions between two vertices can be told apart, while bidirectional
+/// tracks are drawn straight with an arrowhead for each direction.
+#[derive(Debug, Clone, Copy)]
+pub struct ArcGeometry {
+    pub s_pos: Point,
+    pub t_pos: Point,
+    pub bidirectional: bool,
+}
+
+impl ArcGeometry {
+    /// Control point of the curve.
+    pub fn arc_point(&self) -> Point {
+        let midpoint = self.midpoint();
+        if self.bidirectional {
+            return midpoint;
+        }
+        let (x, y) = self.offshoot(0.2);
+        (midpoint.0 + x, midpoint.1 + y)
+    }
+
+    /// Width of the line of an arc with `capacity`, given the largest capacity `max`.
+    pub fn line_width(capacity: usize, max: f32) -> f32 {
+        let minimum_width = 3.;
+        let scaling_factor = 25.;
+        let fraction = (capacity as f32) / max;
+        minimum_width + scaling_factor * (0.5 * (4. * fraction - 2.).tanh() + 0.5)
+    }
+
+    /// Centre of the arrowhead, which lies on the curve.
+    pub fn arrow_position(&self) -> Point {
+        if self.bidirectional {
+            let (s, t) = (self.s_pos, self.t_pos);
+            return (s.0 + 0.65 * (t.0 - s.0), s.1 + 0.65 * (t.1 - s.1));
+        }
+        let midpoint = self.midpoint();
+        let (x, y) = self.offshoot(0.1);
+        (midpoint.0 + x, midpoint.1 + y)
+    }
+
+    /// Counterclockwise angle in radians by which an arrowhead pointing upwards has to be
+    /// rotated to point from `s_pos` to `t_pos`.
+    pub fn arrow_angle(&self) -> f32 {
+        let (x, y) = (self.s_pos.0 - self.t_pos.0, self.s_pos.1 - self.t_pos.1);
+        x.atan2(-y)
+    }
+
+    /// Corners of a triangular arrowhead with the given circumradius, its tip first.
+    pub fn arrow_points(&self, radius: f32) -> [Point; 3] {
+        let (x, y) = self.arrow_position();
+        let angle = self.arrow_angle();
+        [0., 1., 2.].map(|i| {
+            let corner = angle + std::f32::consts::FRAC_PI_2 + i * std::f32::consts::TAU / 3.;
+            (x + radius * corner.cos(), y + radius * corner.sin())
+        })
+    }
+
+    /// The same arc in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        ArcGeometry {
+            s_pos: self.t_pos,
+            t_pos: self.s_pos,
+            ..*self
+        }
+    }
+
+    fn midpoint(&self) -> Point {
+        (
+            0.5 * (self.s_pos.0 + self.t_pos.0),
+            0.5 * (self.s_pos.1 + self.t_pos.1),
+        )
+    }
+
+    /// Offset orthogonal to the arc, to its left, as a fraction of its length.
+    fn offshoot(&self, fraction: f32) -> Point {
+        let (x, y) = (self.s_pos.0 - self.t_pos.0, self.s_pos.1 - self.t_pos.1);
+        (fraction * y, -fraction * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrow_points_to_target() {
+        let arc = ArcGeometry {
+            s_pos: (0., 0.),
+            t_pos: (10., 0.),
+            bidirectional: true,
+        };
+        let [tip, ..] = arc.arrow_points(1.);
+
+        assert!((tip.0 - 7.5).abs() < 1e-5 && tip.1.abs() < 1e-5);
+        assert_eq!((5., 0.), arc.arc_point());
+    }
+}
@@ -3,9 +3,12 @@
 use super::util::*;
 use grb::prelude::*;
 
-use crate::{network::ScenarioSolution, Matrix, Network, Result, SolverError};
+use crate::{network::ScenarioSolution, Matrix, Network, Result};
 
-pub(crate) fn gurobi_full(network: &mut Network) -> Result<Vec<ScenarioSolution>> {
+pub(crate) fn gurobi_full(
+    network: &mut Network,
+    warm_start: Option<&[ScenarioSolution]>,
+) -> Result<Vec<ScenarioSolution>> {
     let slack_values = network.options.slack_fn.apply(&network.balances);
 
     let env = match log::log_enabled!(log::Level::Debug) {
@@ -18,25 +21,37 @@ pub(crate) fn gurobi_full(network: &mut Network) -> Result<Vec<ScenarioSolution>
     let mut scenario_flows = Vec::new();
     let mut scenario_commodity_flows = Vec::new();
     let mut scenario_slack = Vec::new();
+    let mut scenario_limits = Vec::new();
 
     for (lambda, balance) in network.balances.iter().enumerate() {
-        let commodity_flows = get_vars(&mut model, network, &network.capacities, lambda, true)?;
+        let capacities = network.scenario_capacities(lambda);
+        let commodity_flows = get_vars(&mut model, network, &capacities, lambda, true)?;
         let arc_loads = get_arc_loads(network, &commodity_flows);
 
+        let arc_groups = network.scenario_arc_groups(lambda, true, None);
+        let vertex_capacities = network.residual_vertex_capacities(None);
+
         add_multi_commodity_flow_constraints(&mut model, &commodity_flows, balance, lambda)?;
         add_capacity_constraints(
             &mut model,
             network,
-            &network.capacities,
+            &capacities,
+            &arc_groups,
             &arc_loads,
             lambda,
             true,
         )?;
+        add_vertex_capacity_constraints(&mut model, &vertex_capacities, &arc_loads, lambda)?;
 
         // Total slack constraints
         let mut slack_variables = Vec::new();
         for (a_0, a_1) in network.fixed_arcs.iter() {
-            slack_variables.push(add_intvar!(model, name: &format!("slack^{lambda}_({a_0},{a_1})"), bounds: 0..slack_values[lambda])?);
+            slack_variables.push(add_quantity_var(
+                &mut model,
+                network,
+                &format!("slack^{lambda}_({a_0},{a_1})"),
+                slack_values[lambda] as f64,
+            )?);
         }
         let _ = model.add_constr(
             &format!("total_slack^{lambda}"),
@@ -46,6 +61,14 @@ pub(crate) fn gurobi_full(network: &mut Network) -> Result<Vec<ScenarioSolution>
         scenario_flows.push(arc_loads);
         scenario_commodity_flows.push(commodity_flows);
         scenario_slack.push(slack_variables);
+        scenario_limits.push(get_capacity_limits(
+            network,
+            &capacities,
+            &arc_groups,
+            &vertex_capacities,
+            lambda,
+            true,
+        ));
     }
 
     // Consistent flow constraints
@@ -63,49 +86,51 @@ pub(crate) fn gurobi_full(network: &mut Network) -> Result<Vec<ScenarioSolution>
         }
     }
 
-    // Helper variable for minimizing network cost
-    let c_max = add_intvar!(model, name: "max_scenario_cost", bounds: 0..)?;
+    // Helper variables for minimizing network cost, one per cost metric
+    let mut objectives = Vec::new();
+    for (i, (metric, weight)) in network.objective_metrics().into_iter().enumerate() {
+        let c_max = add_ctsvar!(model, name: &format!("max_scenario_cost_{i}"), bounds: 0..)?;
 
-    // Scenario cost constraints
-    for (lambda, scenario_flow) in scenario_flows.iter().enumerate() {
-        let scenario_cost = network
-            .costs
-            .indices()
-            .map(|(u, v)| scenario_flow.get(u, v).clone() * *network.costs.get(u, v))
-            .grb_sum();
-        let _ = model.add_constr(
-            &format!("scenario_cost_{lambda}"),
-            c!(scenario_cost <= c_max),
+        // Scenario cost constraints
+        for (lambda, scenario_flow) in scenario_flows.iter().enumerate() {
+            let scenario_cost = metric
+                .indices()
+                .map(|(u, v)| scenario_flow.get(u, v).clone() * *metric.get(u, v))
+                .grb_sum();
+            let _ = model.add_constr(
+                &format!("scenario_cost_{i}_{lambda}"),
+                c!(scenario_cost <= c_max),
+            )?;
+        }
+        objectives.push((Expr::from(c_max), weight));
+    }
+
+    if let Some(solutions) = warm_start {
+        set_start(
+            &mut model,
+            network,
+            solutions,
+            &scenario_commodity_flows,
+            &scenario_slack,
+            &slack_values,
         )?;
     }
 
     // Objective function
-    model.set_objective(c_max, Minimize)?;
-    model.write("network.lp")?;
-
-    model.optimize()?;
-    match model.status()? {
-        Status::Optimal => {}
-        Status::SubOptimal => {}
-        _ => return Err(SolverError::GurobiSolutionError(0)),
-    }
+    optimize_objectives(
+        &mut model,
+        &network.options.objective_fn,
+        objectives,
+        "network.lp",
+        0,
+    )?;
 
     let mut scenario_arc_loads = Vec::new();
-    for scenario_flow in scenario_commodity_flows {
-        let mut arc_loads = Matrix::filled_with(0, network.vertices.len(), network.vertices.len());
-        for s_t_flow in scenario_flow.elements() {
-            let result = model.get_obj_attr_batch(attr::X, s_t_flow.elements().copied())?;
-            arc_loads = arc_loads.add(&Matrix::from_elements(
-                result
-                    .iter()
-                    .map(|e| *e as usize)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-                arc_loads.num_rows(),
-                arc_loads.num_columns(),
-            ));
-        }
-        scenario_arc_loads.push(arc_loads);
+    let mut scenario_flows = Vec::new();
+    for (scenario_flow, limits) in scenario_commodity_flows.iter().zip(scenario_limits) {
+        let flows = get_commodity_flows(&model, network, scenario_flow, &limits)?;
+        scenario_arc_loads.push(sum_commodity_flows(network, &flows));
+        scenario_flows.push(flows);
     }
 
     let mut solutions = Vec::new();
@@ -129,8 +154,110 @@ pub(crate) fn gurobi_full(network: &mut Network) -> Result<Vec<ScenarioSolution>
                 network.vertices.len(),
             ),
             arc_loads: scenario_arc_loads[i].clone(),
+            unmet_demand: Matrix::filled_with(0, network.vertices.len(), network.vertices.len()),
+            commodity_flows: scenario_flows[i].clone(),
+            paths: decompose_commodity_flows(&scenario_flows[i]),
         })
     }
 
     Ok(solutions)
 }
+
+/// Passes an existing solution to Gurobi as a MIP start. The flows of OD pairs which have not
+/// been routed completely are left for Gurobi to fill in, as are the slack variables unless the
+/// solution adheres to the slack budgets.
+fn set_start(
+    model: &mut Model,
+    network: &Network,
+    solutions: &[ScenarioSolution],
+    scenario_commodity_flows: &[Matrix<Matrix<Var>>],
+    scenario_slack: &[Vec<Var>],
+    slack_values: &[usize],
+) -> Result<()> {
+    let mut complete = true;
+    for solution in solutions {
+        let commodity_flows = &scenario_commodity_flows[solution.id];
+        for (s, t) in commodity_flows.indices() {
+            let flow = solution
+                .commodity_flows
+                .iter()
+                .find(|flow| (flow.origin, flow.destination) == (s, t));
+            let routed = *solution.supply_remaining.get(s, t) == 0
+                && (flow.is_some() || *network.balances[solution.id].get(s, t) == 0);
+            if !routed {
+                complete = false;
+                continue;
+            }
+            let s_t_flows = commodity_flows.get(s, t);
+            model.set_obj_attr_batch(
+                attr::Start,
+                s_t_flows.indices().map(|(u, v)| {
+                    (
+                        *s_t_flows.get(u, v),
+                        flow.map_or(0, |flow| flow.arc_load(u, v)) as f64,
+                    )
+                }),
+            )?;
+        }
+    }
+    if !complete {
+        log::warn!("The starting solution does not route all supply, which is left to Gurobi.");
+        return Ok(());
+    }
+
+    for solution in solutions {
+        let slack = network
+            .fixed_arcs
+            .iter()
+            .map(|&(a_0, a_1)| {
+                let consistent_flow = solutions
+                    .iter()
+                    .map(|solution| *solution.arc_loads.get(a_0, a_1))
+                    .max()
+                    .unwrap_or(0);
+                consistent_flow - *solution.arc_loads.get(a_0, a_1)
+            })
+            .collect::<Vec<_>>();
+        if slack.iter().sum::<usize>() > slack_values[solution.id] {
+            log::warn!(
+                "The starting solution exceeds the slack budget of scenario {}, and is likely rejected by Gurobi.",
+                solution.id
+            );
+            return Ok(());
+        }
+        model.set_obj_attr_batch(
+            attr::Start,
+            scenario_slack[solution.id]
+                .iter()
+                .zip(slack)
+                .map(|(var, slack)| (*var, slack as f64)),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::tests::solved_network;
+    use crate::RemainderSolveMethod;
+
+    #[test]
+    #[ignore = "requires a Gurobi licence"]
+    fn test_warm_start() {
+        let mut network = solved_network();
+        network.options.remainder_solve_method = RemainderSolveMethod::Ilp;
+        let greedy_cost = network.cost().unwrap();
+        network.warm_start_full_ilp().unwrap();
+        network.validate_solution().unwrap();
+        assert!(network.cost().unwrap() <= greedy_cost);
+
+        // supply which the start leaves unrouted is up to Gurobi
+        let mut network = solved_network();
+        network.options.remainder_solve_method = RemainderSolveMethod::Ilp;
+        network.solutions.as_mut().unwrap()[1]
+            .supply_remaining
+            .set(0, 2, 1);
+        network.warm_start_full_ilp().unwrap();
+        network.validate_solution().unwrap();
+    }
+}
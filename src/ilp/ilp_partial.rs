@@ -3,7 +3,10 @@
 use super::util::*;
 use grb::prelude::*;
 
-use crate::{network::ScenarioSolution, Matrix, Network, Result, SolverError};
+use crate::{
+    network::{record_commodity_flow, record_path, ScenarioSolution},
+    Matrix, Network, Result,
+};
 
 pub(crate) fn gurobi_partial(
     network: &mut Network,
@@ -18,14 +21,17 @@ pub(crate) fn gurobi_partial(
             .map(|(i, b)| ScenarioSolution::new(i, b))
             .collect::<Vec<_>>(),
     };
-    for (lambda, scenario) in state.iter_mut().enumerate() {
+    for scenario in state.iter_mut() {
+        let lambda = scenario.id;
         let env = match log::log_enabled!(log::Level::Debug) {
             true => Env::new("gurobi.log")?,
             false => get_quiet_env(),
         };
 
         let mut model = Model::with_env(&format!("scenario_{lambda}"), env)?;
-        let capacities = &network.capacities.subtract(&scenario.arc_loads);
+        let capacities = &network
+            .scenario_capacities(lambda)
+            .subtract(&scenario.arc_loads);
 
         let commodity_flows = get_vars(
             &mut model,
@@ -42,47 +48,77 @@ pub(crate) fn gurobi_partial(
             &scenario.supply_remaining,
             lambda,
         )?;
+        let arc_groups = network.scenario_arc_groups(
+            lambda,
+            lift_capacity_constraints,
+            Some(&scenario.arc_loads),
+        );
+        let vertex_capacities = network.residual_vertex_capacities(Some(&scenario.arc_loads));
         add_capacity_constraints(
             &mut model,
             network,
             capacities,
+            &arc_groups,
             &arc_loads,
             lambda,
             lift_capacity_constraints,
         )?;
+        add_vertex_capacity_constraints(&mut model, &vertex_capacities, &arc_loads, lambda)?;
 
         // Objective function
-        let total_scenario_cost = network
-            .costs
-            .indices()
-            .map(|(u, v)| arc_loads.get(u, v).clone() * *network.costs.get(u, v))
-            .grb_sum();
-        model.set_objective(total_scenario_cost, Minimize)?;
-        model.write(&format!("scenario_{lambda}.lp"))?;
-
-        model.optimize()?;
-        match model.status()? {
-            Status::Optimal => {}
-            Status::SubOptimal => {}
-            _ => return Err(SolverError::GurobiSolutionError(scenario.id)),
-        }
+        let objectives = network
+            .objective_metrics()
+            .into_iter()
+            .map(|(metric, weight)| {
+                let total_scenario_cost = metric
+                    .indices()
+                    .map(|(u, v)| arc_loads.get(u, v).clone() * *metric.get(u, v))
+                    .grb_sum();
+                (total_scenario_cost, weight)
+            })
+            .collect();
+        optimize_objectives(
+            &mut model,
+            &network.options.objective_fn,
+            objectives,
+            &format!("scenario_{lambda}.lp"),
+            lambda,
+        )?;
 
-        for commodity_flow in commodity_flows.elements() {
-            let result = model.get_obj_attr_batch(attr::X, commodity_flow.elements().copied())?;
-            scenario.arc_loads = scenario.arc_loads.add(&Matrix::from_elements(
-                result
-                    .iter()
-                    .map(|e| *e as usize)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-                scenario.arc_loads.num_rows(),
-                scenario.arc_loads.num_columns(),
-            ));
-            scenario.supply_remaining = Matrix::filled_with(
-                0,
-                scenario.supply_remaining.num_rows(),
-                scenario.supply_remaining.num_columns(),
-            );
+        let limits = get_capacity_limits(
+            network,
+            capacities,
+            &arc_groups,
+            &vertex_capacities,
+            lambda,
+            lift_capacity_constraints,
+        );
+        let flows = get_commodity_flows(&model, network, &commodity_flows, &limits)?;
+        scenario.arc_loads = scenario
+            .arc_loads
+            .add(&sum_commodity_flows(network, &flows));
+        scenario.supply_remaining = Matrix::filled_with(
+            0,
+            scenario.supply_remaining.num_rows(),
+            scenario.supply_remaining.num_columns(),
+        );
+        for flow in flows {
+            for path in decompose_commodity_flows(std::slice::from_ref(&flow)) {
+                record_path(
+                    &mut scenario.paths,
+                    (path.origin, path.destination),
+                    &path.vertices,
+                    path.amount,
+                );
+            }
+            for (u, v, load) in flow.arc_loads {
+                record_commodity_flow(
+                    &mut scenario.commodity_flows,
+                    (flow.origin, flow.destination),
+                    (u, v),
+                    load,
+                );
+            }
         }
     }
 
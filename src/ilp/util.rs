@@ -1,8 +1,11 @@
 #![allow(clippy::useless_conversion)] // Clippy doesn't like the "c!()" constraints macro
-                                      //
+#![allow(clippy::unnecessary_cast)] // nor the casts of the bounds in "add_intvar!()"
 use crate::{
-    algorithms::floyd_warshall, auxiliary::generate_intermediate_arc_sets, matrix::Matrix,
-    DeltaFunction, Network, Result,
+    algorithms::{decompose_flow, floyd_warshall, round_flows},
+    auxiliary::generate_intermediate_arc_sets,
+    matrix::Matrix,
+    ArcGroup, CommodityFlow, DeltaFunction, FlowPath, Network, ObjectiveFunction, Result,
+    SolverError,
 };
 use grb::prelude::*;
 
@@ -21,6 +24,21 @@ pub(super) fn get_arc_sets(
     generate_intermediate_arc_sets(&dist, costs, capacities, delta_fn)
 }
 
+/// Adds a non-negative variable for a quantity of supply, which is integral unless the options
+/// ask for continuous flows.
+pub(super) fn add_quantity_var(
+    model: &mut Model,
+    network: &Network,
+    name: &str,
+    upper_bound: f64,
+) -> Result<Var> {
+    let var = match network.options.continuous_flows {
+        true => add_ctsvar!(model, name: name, bounds: 0.0..upper_bound)?,
+        false => add_intvar!(model, name: name, bounds: 0.0..upper_bound)?,
+    };
+    Ok(var)
+}
+
 pub(super) fn get_vars(
     model: &mut Model,
     network: &Network,
@@ -28,7 +46,12 @@ pub(super) fn get_vars(
     lambda: usize,
     lift_capacity_constraints: bool,
 ) -> Result<Matrix<Matrix<Var>>> {
-    let arc_sets = get_arc_sets(capacities, &network.costs, &network.options.delta_fn);
+    let mut arc_sets = get_arc_sets(
+        capacities,
+        network.routing_costs(),
+        &network.options.delta_fn,
+    );
+    network.restrict_penalty_arcs(&mut arc_sets);
     let mut commodity_flows: Matrix<Matrix<Var>> = Matrix::filled_with(
         Matrix::empty(),
         network.vertices.len(),
@@ -39,17 +62,21 @@ pub(super) fn get_vars(
         let mut s_t_flows = Vec::new();
         for (u, v) in capacities.indices() {
             let upper_bound = if *arc_sets.get(s, t).get(u, v) {
-                // Fixed arcs have unlimited capacity
-                if lift_capacity_constraints && network.fixed_arcs.contains(&(u, v)) {
+                if lift_capacity_constraints && network.is_lifted(lambda, u, v) {
                     usize::MAX
                 } else {
-                    *network.capacities.get(u, v)
+                    *capacities.get(u, v)
                 }
             } else {
                 0
             };
             // Combines non-negative, capacity, and intermediate arc set bounds
-            s_t_flows.push(add_intvar!(model, name: &format!("f^{lambda}_({s},{t})(({u},{v}))"), bounds: 0..upper_bound)?);
+            s_t_flows.push(add_quantity_var(
+                model,
+                network,
+                &format!("f^{lambda}_({s},{t})(({u},{v}))"),
+                upper_bound as f64,
+            )?);
         }
 
         let s_t_flows =
@@ -81,6 +108,114 @@ pub(super) fn get_arc_loads(
     )
 }
 
+/// Capacities which the flows of scenario `lambda` have to respect together, as groups of arcs:
+/// every arc which is not lifted on its own, the arc groups, and the arcs into every vertex with
+/// a capacity.
+pub(super) fn get_capacity_limits(
+    network: &Network,
+    capacities: &Matrix<usize>,
+    arc_groups: &[ArcGroup],
+    vertex_capacities: &[Option<usize>],
+    lambda: usize,
+    lift_capacity_constraints: bool,
+) -> Vec<ArcGroup> {
+    let arcs = capacities
+        .indices()
+        .filter(|&(u, v)| !(lift_capacity_constraints && network.is_lifted(lambda, u, v)))
+        .map(|(u, v)| ArcGroup {
+            arcs: vec![(u, v)],
+            capacity: *capacities.get(u, v),
+        });
+    let vertices = vertex_capacities
+        .iter()
+        .enumerate()
+        .filter_map(|(v, capacity)| {
+            capacity.map(|capacity| ArcGroup {
+                arcs: (0..vertex_capacities.len()).map(|u| (u, v)).collect(),
+                capacity,
+            })
+        });
+    arcs.chain(arc_groups.iter().cloned())
+        .chain(vertices)
+        .collect()
+}
+
+/// Reads the flow of every OD pair with a non-zero flow from a solved model. Continuous flows
+/// are rounded path by path, so that each of them is still conserved, and jointly, so that they
+/// stay within the capacities of `limits`.
+pub(super) fn get_commodity_flows(
+    model: &Model,
+    network: &Network,
+    commodity_flows: &Matrix<Matrix<Var>>,
+    limits: &[ArcGroup],
+) -> Result<Vec<CommodityFlow>> {
+    let mut fractional_flows = Vec::new();
+    for (s, t) in commodity_flows.indices() {
+        let s_t_flows = commodity_flows.get(s, t);
+        let result = model.get_obj_attr_batch(attr::X, s_t_flows.elements().copied())?;
+        let arc_loads = s_t_flows
+            .indices()
+            .zip(result)
+            .map(|((u, v), x)| (u, v, x))
+            .collect::<Vec<_>>();
+        fractional_flows.push((s, t, arc_loads));
+    }
+    let rounded_flows = match network.options.continuous_flows {
+        true => round_flows(&fractional_flows, limits),
+        false => fractional_flows
+            .iter()
+            .map(|(_, _, arc_loads)| {
+                arc_loads
+                    .iter()
+                    .map(|&(u, v, x)| (u, v, x.round() as usize))
+                    .filter(|(_, _, load)| *load > 0)
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+    };
+    Ok(fractional_flows
+        .into_iter()
+        .zip(rounded_flows)
+        .filter(|(_, arc_loads)| !arc_loads.is_empty())
+        .map(|((s, t, _), arc_loads)| CommodityFlow {
+            origin: s,
+            destination: t,
+            arc_loads,
+        })
+        .collect())
+}
+
+/// Arc loads of all OD pairs' flows together.
+pub(super) fn sum_commodity_flows(
+    network: &Network,
+    commodity_flows: &[CommodityFlow],
+) -> Matrix<usize> {
+    let mut arc_loads = Matrix::filled_with(0, network.vertices.len(), network.vertices.len());
+    for flow in commodity_flows {
+        for &(u, v, load) in flow.arc_loads.iter() {
+            arc_loads.set(u, v, arc_loads.get(u, v) + load);
+        }
+    }
+    arc_loads
+}
+
+/// Splits the flow of every OD pair into the paths it takes.
+pub(super) fn decompose_commodity_flows(commodity_flows: &[CommodityFlow]) -> Vec<FlowPath> {
+    commodity_flows
+        .iter()
+        .flat_map(|flow| {
+            decompose_flow(&flow.arc_loads, flow.origin, flow.destination)
+                .into_iter()
+                .map(|(vertices, amount)| FlowPath {
+                    origin: flow.origin,
+                    destination: flow.destination,
+                    vertices,
+                    amount,
+                })
+        })
+        .collect()
+}
+
 pub(super) fn add_multi_commodity_flow_constraints(
     model: &mut Model,
     commodity_flows: &Matrix<Matrix<Var>>,
@@ -118,13 +253,14 @@ pub(super) fn add_capacity_constraints(
     model: &mut Model,
     network: &Network,
     capacities: &Matrix<usize>,
+    arc_groups: &[ArcGroup],
     arc_loads: &Matrix<Expr>,
     lambda: usize,
     lift_capacity_constraints: bool,
 ) -> Result<()> {
     for (u, v) in capacities.indices() {
-        // Fixed arcs have unlimited capacity
-        if lift_capacity_constraints && network.fixed_arcs.contains(&(u, v)) {
+        // Fixed arcs have unlimited capacity, unless the scenario overrides it
+        if lift_capacity_constraints && network.is_lifted(lambda, u, v) {
             continue;
         }
         let _ = model.add_constr(
@@ -132,5 +268,120 @@ pub(super) fn add_capacity_constraints(
             c!(arc_loads.get(u, v).clone() <= *capacities.get(u, v)),
         )?;
     }
+    for (i, group) in arc_groups.iter().enumerate() {
+        let _ = model.add_constr(
+            &format!("shared_capacity^{lambda}_{i}"),
+            c!(group
+                .arcs
+                .iter()
+                .map(|(u, v)| arc_loads.get(*u, *v).clone())
+                .grb_sum()
+                <= group.capacity),
+        )?;
+    }
     Ok(())
 }
+
+pub(super) fn add_vertex_capacity_constraints(
+    model: &mut Model,
+    vertex_capacities: &[Option<usize>],
+    arc_loads: &Matrix<Expr>,
+    lambda: usize,
+) -> Result<()> {
+    for (v, capacity) in vertex_capacities.iter().enumerate() {
+        let Some(capacity) = capacity else {
+            continue;
+        };
+        let _ = model.add_constr(
+            &format!("vertex_capacity^{lambda}_{v}"),
+            c!((0..vertex_capacities.len())
+                .map(|u| arc_loads.get(u, v).clone())
+                .grb_sum()
+                <= *capacity),
+        )?;
+    }
+    Ok(())
+}
+
+/// Sets the objective from the per-metric costs and their weights, then optimizes the model.
+/// Lexicographic objectives are minimized one metric at a time, each one bounded by the optima of
+/// the metrics before it.
+pub(super) fn optimize_objectives(
+    model: &mut Model,
+    objective_fn: &ObjectiveFunction,
+    objectives: Vec<(Expr, f64)>,
+    filename: &str,
+    scenario_id: usize,
+) -> Result<()> {
+    match objective_fn {
+        ObjectiveFunction::Lexicographic => {
+            for (i, (objective, _)) in objectives.into_iter().enumerate() {
+                model.set_objective(objective.clone(), Minimize)?;
+                model.write(filename)?;
+                optimize(model, scenario_id)?;
+
+                let optimum = model.get_attr(attr::ObjVal)? + 1e-6;
+                let _ =
+                    model.add_constr(&format!("lexicographic_{i}"), c!(objective <= optimum))?;
+            }
+            Ok(())
+        }
+        ObjectiveFunction::Single | ObjectiveFunction::Weighted => {
+            let objective = objectives
+                .into_iter()
+                .map(|(objective, weight)| objective * weight)
+                .grb_sum();
+            model.set_objective(objective, Minimize)?;
+            model.write(filename)?;
+            optimize(model, scenario_id)
+        }
+    }
+}
+
+fn optimize(model: &mut Model, scenario_id: usize) -> Result<()> {
+    model.optimize()?;
+    match model.status()? {
+        Status::Optimal => Ok(()),
+        Status::SubOptimal => Ok(()),
+        _ => Err(SolverError::GurobiSolutionError(scenario_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimizes `y` and `3 x` under `x + y >= 2`, returning the optimal `(x, y)`.
+    fn optimize_xy(objective_fn: &ObjectiveFunction) -> (f64, f64) {
+        let mut model = Model::with_env("objectives", get_quiet_env()).unwrap();
+        let x = add_intvar!(model, name: "x", bounds: 0..).unwrap();
+        let y = add_intvar!(model, name: "y", bounds: 0..).unwrap();
+        model.add_constr("demand", c!(x + y >= 2)).unwrap();
+        let filename = std::env::temp_dir().join(format!(
+            "robmcf_objectives_{objective_fn:?}_{}.lp",
+            std::process::id()
+        ));
+        optimize_objectives(
+            &mut model,
+            objective_fn,
+            vec![(y.into(), 1.0), (x.into(), 3.0)],
+            filename.to_str().unwrap(),
+            0,
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(filename);
+        (
+            model.get_obj_attr(attr::X, &x).unwrap(),
+            model.get_obj_attr(attr::X, &y).unwrap(),
+        )
+    }
+
+    #[test]
+    #[ignore = "requires a Gurobi licence"]
+    fn test_optimize_objectives() {
+        // y comes first, whatever its weight
+        assert_eq!((2.0, 0.0), optimize_xy(&ObjectiveFunction::Lexicographic));
+        // y is three times cheaper than x
+        assert_eq!((0.0, 2.0), optimize_xy(&ObjectiveFunction::Weighted));
+    }
+}
@@ -1,13 +1,20 @@
 mod algorithms;
 mod auxiliary;
+mod geometry;
 mod ilp;
 mod matrix;
 mod network;
 mod options;
 mod util;
 
+pub use geometry::ArcGeometry;
 pub use matrix::Matrix;
+pub use network::ArcGroup;
 pub use network::Network;
 pub use network::Vertex;
+pub use network::{
+    ArcChange, Bottleneck, Change, CommodityFlow, FlowPath, InfeasibilityDiagnosis, LoadShading,
+    Projection, RouteChange, ScenarioComparison, SolutionComparison, SolutionViolation,
+};
 pub use options::*;
 pub use util::{Result, SolverError};
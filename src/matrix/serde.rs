@@ -1,4 +1,12 @@
-use serde::{de::Visitor, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+//! Human-readable formats store a matrix densely as a list of rows. Binary formats store it
+//! sparsely as its shape along with the triples "row, column, element" of all elements which
+//! differ from the default, since most matrices of a network are mostly zero.
+
+use serde::{
+    de::{Error as _, Visitor},
+    ser::{SerializeSeq, SerializeTuple},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use super::Matrix;
 
@@ -27,28 +35,85 @@ where
     }
 }
 
+struct SparseMatrixVisitor<T> {
+    _phantom: std::marker::PhantomData<T>,
+}
+impl<'de, T> Visitor<'de> for SparseMatrixVisitor<T>
+where
+    T: Deserialize<'de> + Clone + Default,
+{
+    type Value = Matrix<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("could not deserialize sparse Matrix")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let missing = || A::Error::custom("sparse Matrix is missing its shape or elements");
+        let rows: usize = seq.next_element()?.ok_or_else(missing)?;
+        let columns: usize = seq.next_element()?.ok_or_else(missing)?;
+        let elements: Vec<(usize, usize, T)> = seq.next_element()?.ok_or_else(missing)?;
+
+        let mut matrix = Matrix::filled_with(T::default(), rows, columns);
+        for (row, column, element) in elements {
+            if row >= rows || column >= columns {
+                return Err(A::Error::custom(format!(
+                    "sparse Matrix of shape ({rows}, {columns}) has an element at ({row}, {column})"
+                )));
+            }
+            matrix.set(row, column, element);
+        }
+        Ok(matrix)
+    }
+}
+
 impl<'de, T> Deserialize<'de> for Matrix<T>
 where
-    T: Deserialize<'de> + Clone,
+    T: Deserialize<'de> + Clone + Default,
 {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_seq(MatrixVisitor {
-            _phantom: std::marker::PhantomData,
-        })
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_seq(MatrixVisitor {
+                _phantom: std::marker::PhantomData,
+            }),
+            false => deserializer.deserialize_tuple(
+                3,
+                SparseMatrixVisitor {
+                    _phantom: std::marker::PhantomData,
+                },
+            ),
+        }
     }
 }
 
 impl<T> Serialize for Matrix<T>
 where
-    T: Serialize + Clone,
+    T: Serialize + Clone + Default + PartialEq,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            let default = T::default();
+            let elements = self
+                .indices()
+                .map(|(row, column)| (row, column, self.get(row, column)))
+                .filter(|(_, _, element)| **element != default)
+                .collect::<Vec<_>>();
+            let mut tuple = serializer.serialize_tuple(3)?;
+            tuple.serialize_element(&self.num_rows())?;
+            tuple.serialize_element(&self.num_columns())?;
+            tuple.serialize_element(&elements)?;
+            return tuple.end();
+        }
+
         let mut seq = serializer.serialize_seq(Some(self.as_rows().len()))?;
         for e in self.as_rows() {
             seq.serialize_element(&e)?;
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Matrix;
+
+/// Arcs sharing a single capacity, e.g. both directions of a single-track line. Every arc still
+/// has to respect its own capacity as well.
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct ArcGroup {
+    pub arcs: Vec<(usize, usize)>,
+    pub capacity: usize,
+}
+
+impl ArcGroup {
+    pub fn bidirectional(s: usize, t: usize, capacity: usize) -> Self {
+        ArcGroup {
+            arcs: vec![(s, t), (t, s)],
+            capacity,
+        }
+    }
+
+    pub fn is_bidirectional(&self) -> bool {
+        self.arcs.len() == 2 && self.arcs[0] == (self.arcs[1].1, self.arcs[1].0)
+    }
+
+    pub fn contains(&self, s: usize, t: usize) -> bool {
+        self.arcs.contains(&(s, t))
+    }
+
+    pub(crate) fn load(&self, arc_loads: &Matrix<usize>) -> usize {
+        self.arcs.iter().map(|(s, t)| *arc_loads.get(*s, *t)).sum()
+    }
+}
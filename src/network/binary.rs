@@ -0,0 +1,87 @@
+//! Compact binary network files: a few magic bytes followed by the network as MessagePack.
+//! Unlike network files in JSON, they store matrices sparsely and quantities in whole quanta.
+
+use std::path::Path;
+
+use crate::Result;
+
+use super::Network;
+
+const MAGIC: &[u8] = b"RMCF\x01";
+
+/// Extension under which networks are written in the binary format.
+pub(super) const EXTENSION: &str = "rmcf";
+
+pub(super) fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub(super) fn is_binary_filename(filename: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .is_some_and(|extension| extension == EXTENSION)
+}
+
+pub(super) fn from_bytes(bytes: &[u8]) -> Result<Network> {
+    Ok(rmp_serde::from_slice(&bytes[MAGIC.len()..])?)
+}
+
+pub(super) fn to_bytes(network: &Network) -> Result<Vec<u8>> {
+    let mut bytes = MAGIC.to_vec();
+    // named fields, so that fields with defaults can be skipped or added later on
+    rmp_serde::encode::write_named(&mut bytes, network)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+    use crate::options::NetworkFormat;
+
+    #[test]
+    fn test_round_trip() {
+        let mut network = solved_network();
+        network.baseline = network.solutions.clone();
+        network.quantum = 0.5;
+
+        let bytes = to_bytes(&network).unwrap();
+        assert!(is_binary(&bytes));
+        let restored = from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&network).unwrap(),
+            serde_json::to_value(&restored).unwrap()
+        );
+        assert!(!restored.capacity_overrides[1].is_empty());
+        assert_eq!(restored.arc_groups.len(), 1);
+        assert_eq!(restored.penalty_arcs, network.penalty_arcs);
+        assert_eq!(restored.baseline.unwrap().len(), 2);
+        assert_eq!(restored.solutions.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_format_from_magic_bytes() {
+        let network = solved_network();
+        // no .rmcf extension, so only the magic bytes tell the formats apart
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_binary_format_{}.json", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        network
+            .serialize_as(filename, NetworkFormat::Binary)
+            .unwrap();
+        let binary = Network::format_of(filename);
+        network
+            .serialize_as(filename, NetworkFormat::Dense)
+            .unwrap();
+        let dense = Network::format_of(filename);
+        std::fs::remove_file(filename).unwrap();
+
+        assert_eq!(binary.unwrap(), NetworkFormat::Binary);
+        assert_eq!(dense.unwrap(), NetworkFormat::Dense);
+        assert!(!is_binary(b"{\"vertices\": []}"));
+        assert!(is_binary_filename("network.rmcf"));
+        assert!(!is_binary_filename("network.json"));
+    }
+}
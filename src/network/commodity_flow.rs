@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// Flow of the supply of a single OD pair through a scenario.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommodityFlow {
+    pub origin: usize,
+    pub destination: usize,
+    /// Arc loads as triples of "s, t, load"
+    pub arc_loads: Vec<(usize, usize, usize)>,
+}
+
+impl CommodityFlow {
+    pub fn arc_load(&self, s: usize, t: usize) -> usize {
+        self.arc_loads
+            .iter()
+            .find(|(a_0, a_1, _)| (*a_0, *a_1) == (s, t))
+            .map_or(0, |(_, _, load)| *load)
+    }
+
+    /// Outflow minus inflow at vertex `v`.
+    pub(crate) fn net_outflow(&self, v: usize) -> i64 {
+        self.arc_loads
+            .iter()
+            .map(|&(s, t, load)| match (s == v, t == v) {
+                (true, false) => load as i64,
+                (false, true) => -(load as i64),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// Adds `amount` to the load of the OD pair `(origin, destination)` on arc `(s, t)`.
+pub(crate) fn record_commodity_flow(
+    commodity_flows: &mut Vec<CommodityFlow>,
+    (origin, destination): (usize, usize),
+    (s, t): (usize, usize),
+    amount: usize,
+) {
+    let index = match commodity_flows
+        .iter()
+        .position(|flow| (flow.origin, flow.destination) == (origin, destination))
+    {
+        Some(index) => index,
+        None => {
+            commodity_flows.push(CommodityFlow {
+                origin,
+                destination,
+                arc_loads: vec![],
+            });
+            commodity_flows.len() - 1
+        }
+    };
+    let arc_loads = &mut commodity_flows[index].arc_loads;
+    match arc_loads
+        .iter_mut()
+        .find(|(a_0, a_1, _)| (*a_0, *a_1) == (s, t))
+    {
+        Some((_, _, load)) => *load += amount,
+        None => arc_loads.push((s, t, amount)),
+    }
+}
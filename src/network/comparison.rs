@@ -0,0 +1,479 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use colored::{Color, ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::{Matrix, Result, SolverError};
+
+use super::{FlowPath, Network, ScenarioSolution, Solution};
+
+/// Differences between two solutions of the same network, from `before` to `after`.
+#[derive(Debug, Serialize)]
+pub struct SolutionComparison {
+    pub cost: Change<usize>,
+    pub robustness_coefficient: Change<f64>,
+    pub scenarios: Vec<ScenarioComparison>,
+    pub consistent_flows: Vec<ArcChange>,
+    /// Differences in consistent flows, for terminal output
+    #[serde(skip)]
+    consistent_flow_difference: Matrix<ColoredString>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScenarioComparison {
+    pub scenario: usize,
+    pub cost: Change<usize>,
+    pub arc_loads: Vec<ArcChange>,
+    pub routes: Vec<RouteChange>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArcChange {
+    pub from: String,
+    pub to: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// An OD pair whose supply takes different paths. Paths are given as lists of vertex names.
+#[derive(Debug, Serialize)]
+pub struct RouteChange {
+    pub origin: String,
+    pub destination: String,
+    pub before: Vec<(Vec<String>, usize)>,
+    pub after: Vec<(Vec<String>, usize)>,
+}
+
+/// A single difference, as written to CSV files.
+#[derive(Debug, Serialize)]
+struct ComparisonRecord {
+    kind: &'static str,
+    scenario: Option<usize>,
+    from: Option<String>,
+    to: Option<String>,
+    before: String,
+    after: String,
+}
+
+impl Change<usize> {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl ArcChange {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl Network {
+    /// Compares the solution of this network with that of `other`.
+    pub fn compare_to(&self, other: &Network) -> Result<SolutionComparison> {
+        match (&self.solutions, &other.solutions) {
+            (Some(before), Some(after)) => compare((self, before), (other, after)),
+            _ => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    /// Compares the baseline of this network with its solution.
+    pub fn compare_to_baseline(&self) -> Result<SolutionComparison> {
+        match (&self.baseline, &self.solutions) {
+            (Some(before), Some(after)) => compare((self, before), (self, after)),
+            (None, _) => Err(SolverError::SkippedBaselineError),
+            (_, None) => Err(SolverError::SkippedSolveError),
+        }
+    }
+}
+
+fn compare(
+    (network_before, before): (&Network, &[ScenarioSolution]),
+    (network_after, after): (&Network, &[ScenarioSolution]),
+) -> Result<SolutionComparison> {
+    if network_before.vertices.len() != network_after.vertices.len() || before.len() != after.len()
+    {
+        return Err(SolverError::NetworkShapeError(format!(
+            "Cannot compare a solution of {} vertices and {} scenarios with one of {} vertices and {} scenarios.",
+            network_before.vertices.len(),
+            before.len(),
+            network_after.vertices.len(),
+            after.len()
+        )));
+    }
+    let name = |v: usize| network_after.vertices[v].to_string();
+
+    let mut fixed_arcs = network_before.fixed_arcs.clone();
+    for arc in network_after.fixed_arcs.iter() {
+        if !fixed_arcs.contains(arc) {
+            fixed_arcs.push(*arc);
+        }
+    }
+    let consistent_before = before.consistent_flows(&fixed_arcs);
+    let consistent_after = after.consistent_flows(&fixed_arcs);
+
+    let mut scenarios = Vec::new();
+    for solution_before in before {
+        // solutions are not necessarily ordered by scenario
+        let Some(solution_after) = after.iter().find(|s| s.id == solution_before.id) else {
+            return Err(SolverError::NetworkShapeError(format!(
+                "Scenario {} has no solution to compare with.",
+                solution_before.id
+            )));
+        };
+        scenarios.push(ScenarioComparison {
+            scenario: solution_before.id,
+            cost: Change {
+                before: solution_before.cost(network_before.routing_costs()),
+                after: solution_after.cost(network_after.routing_costs()),
+            },
+            arc_loads: solution_after
+                .arc_loads
+                .indices()
+                .map(|(s, t)| ArcChange {
+                    from: name(s),
+                    to: name(t),
+                    before: *solution_before.arc_loads.get(s, t),
+                    after: *solution_after.arc_loads.get(s, t),
+                })
+                .filter(|change| change.before != change.after)
+                .collect(),
+            routes: route_changes(network_after, solution_before, solution_after),
+        });
+    }
+    scenarios.sort_by_key(|scenario| scenario.scenario);
+
+    Ok(SolutionComparison {
+        cost: Change {
+            before: before.cost(
+                network_before.routing_costs(),
+                &network_before.options.cost_fn,
+            ),
+            after: after.cost(
+                network_after.routing_costs(),
+                &network_after.options.cost_fn,
+            ),
+        },
+        robustness_coefficient: Change {
+            before: before.robustness_coefficient(&network_before.fixed_arcs),
+            after: after.robustness_coefficient(&network_after.fixed_arcs),
+        },
+        scenarios,
+        consistent_flows: fixed_arcs
+            .iter()
+            .map(|&(s, t)| ArcChange {
+                from: name(s),
+                to: name(t),
+                before: *consistent_before.get(s, t) as usize,
+                after: *consistent_after.get(s, t) as usize,
+            })
+            .filter(|change| change.before != change.after)
+            .collect(),
+        consistent_flow_difference: after.highlight_difference_to(before, &fixed_arcs),
+    })
+}
+
+/// OD pairs whose paths differ. Solutions without recorded paths are not compared.
+fn route_changes(
+    network: &Network,
+    before: &ScenarioSolution,
+    after: &ScenarioSolution,
+) -> Vec<RouteChange> {
+    if before.paths.is_empty() || after.paths.is_empty() {
+        return vec![];
+    }
+    let name = |v: usize| network.vertices[v].to_string();
+    let routes = |solution: &ScenarioSolution, s: usize, t: usize| {
+        let mut routes = solution
+            .routes(s, t)
+            .map(|path: &FlowPath| (path.vertices.clone(), path.amount))
+            .collect::<Vec<_>>();
+        routes.sort();
+        routes
+    };
+    let named = |routes: Vec<(Vec<usize>, usize)>| {
+        routes
+            .into_iter()
+            .map(|(vertices, amount)| (vertices.into_iter().map(name).collect(), amount))
+            .collect()
+    };
+
+    after
+        .arc_loads
+        .indices()
+        .filter(|&(s, t)| s != t)
+        .map(|(s, t)| (s, t, routes(before, s, t), routes(after, s, t)))
+        .filter(|(_, _, routes_before, routes_after)| routes_before != routes_after)
+        .map(|(s, t, routes_before, routes_after)| RouteChange {
+            origin: name(s),
+            destination: name(t),
+            before: named(routes_before),
+            after: named(routes_after),
+        })
+        .collect()
+}
+
+impl SolutionComparison {
+    pub fn serialize(&self, filename: &str) -> Result<()> {
+        std::fs::write(filename, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Writes one row per difference.
+    pub fn export(&self, filename: &str) -> Result<()> {
+        let mut wtr = csv::Writer::from_path(filename)?;
+        wtr.serialize(ComparisonRecord {
+            kind: "cost",
+            scenario: None,
+            from: None,
+            to: None,
+            before: self.cost.before.to_string(),
+            after: self.cost.after.to_string(),
+        })?;
+        wtr.serialize(ComparisonRecord {
+            kind: "robustness_coefficient",
+            scenario: None,
+            from: None,
+            to: None,
+            before: self.robustness_coefficient.before.to_string(),
+            after: self.robustness_coefficient.after.to_string(),
+        })?;
+        for change in self.consistent_flows.iter() {
+            wtr.serialize(ComparisonRecord {
+                kind: "consistent_flow",
+                scenario: None,
+                from: Some(change.from.clone()),
+                to: Some(change.to.clone()),
+                before: change.before.to_string(),
+                after: change.after.to_string(),
+            })?;
+        }
+        for scenario in self.scenarios.iter() {
+            wtr.serialize(ComparisonRecord {
+                kind: "scenario_cost",
+                scenario: Some(scenario.scenario),
+                from: None,
+                to: None,
+                before: scenario.cost.before.to_string(),
+                after: scenario.cost.after.to_string(),
+            })?;
+            for change in scenario.arc_loads.iter() {
+                wtr.serialize(ComparisonRecord {
+                    kind: "arc_load",
+                    scenario: Some(scenario.scenario),
+                    from: Some(change.from.clone()),
+                    to: Some(change.to.clone()),
+                    before: change.before.to_string(),
+                    after: change.after.to_string(),
+                })?;
+            }
+            for change in scenario.routes.iter() {
+                wtr.serialize(ComparisonRecord {
+                    kind: "route",
+                    scenario: Some(scenario.scenario),
+                    from: Some(change.origin.clone()),
+                    to: Some(change.destination.clone()),
+                    before: display_routes(&change.before),
+                    after: display_routes(&change.after),
+                })?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+fn display_routes(routes: &[(Vec<String>, usize)]) -> String {
+    if routes.is_empty() {
+        return "no route".to_string();
+    }
+    routes
+        .iter()
+        .map(|(vertices, amount)| format!("{}: {}", vertices.join("->"), amount))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Colours a delta green if it is an improvement, i.e. has the same sign as `better`.
+fn colorize_delta(delta: i64, better: Ordering) -> ColoredString {
+    let string = format!("{delta:+}");
+    match delta.cmp(&0) {
+        Ordering::Equal => string.color(Color::Blue),
+        ordering if ordering == better => string.color(Color::Green),
+        _ => string.color(Color::Red),
+    }
+}
+
+impl Display for SolutionComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut string_repr = vec![format!(
+            "The network cost changes from {} to {} ({}), the robustness coefficient from η = {:4.3} to η = {:4.3}.",
+            self.cost.before,
+            self.cost.after,
+            colorize_delta(self.cost.delta(), Ordering::Less),
+            self.robustness_coefficient.before,
+            self.robustness_coefficient.after,
+        )];
+        string_repr.push(String::new());
+        string_repr.push(format!(
+            "The consistent flows change as follows:\n{}",
+            self.consistent_flow_difference
+        ));
+
+        for scenario in self.scenarios.iter() {
+            string_repr.push(String::new());
+            string_repr.push(format!(
+                "Scenario {}, with cost {} -> {} ({}):",
+                scenario.scenario,
+                scenario.cost.before,
+                scenario.cost.after,
+                colorize_delta(scenario.cost.delta(), Ordering::Less)
+            ));
+            if scenario.arc_loads.is_empty() {
+                string_repr.push("  No arc loads changed.".to_string());
+            }
+            for change in scenario.arc_loads.iter() {
+                string_repr.push(format!(
+                    "  ({}->{}): {} -> {} ({})",
+                    change.from,
+                    change.to,
+                    change.before,
+                    change.after,
+                    format!("{:+}", change.delta()).color(Color::Yellow)
+                ));
+            }
+            for change in scenario.routes.iter() {
+                string_repr.push(format!(
+                    "  Route of ({}->{}) changed from {} to {}",
+                    change.origin,
+                    change.destination,
+                    display_routes(&change.before),
+                    display_routes(&change.after)
+                ));
+            }
+        }
+        write!(f, "{}", string_repr.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    /// The solved network with a baseline in which scenario 0 leaves its demand unmet.
+    fn network() -> Network {
+        let mut network = solved_network();
+        let mut baseline = network.solutions.clone().unwrap();
+        // the greedy does not necessarily put its solutions in order
+        let unmet = baseline
+            .iter_mut()
+            .find(|solution| solution.id == 0)
+            .unwrap();
+        unmet.arc_loads = Matrix::filled_with(0, 3, 3);
+        unmet.unmet_demand.set(0, 2, 1);
+        unmet.paths = vec![FlowPath {
+            origin: 0,
+            destination: 2,
+            vertices: vec![0, 2],
+            amount: 1,
+        }];
+        network.baseline = Some(baseline);
+        network
+    }
+
+    #[test]
+    fn test_compare_to_baseline() {
+        let comparison = network().compare_to_baseline().unwrap();
+        assert_eq!(0, comparison.cost.delta());
+
+        let unmet = &comparison.scenarios[0];
+        assert_eq!((0, 2), (unmet.cost.before, unmet.cost.after));
+        assert_eq!(
+            vec![("a", "b", 0, 1), ("b", "c", 0, 1)],
+            unmet
+                .arc_loads
+                .iter()
+                .map(|c| (c.from.as_str(), c.to.as_str(), c.before, c.after))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, unmet.routes.len());
+        assert_eq!(
+            vec![(vec!["a".to_string(), "c".to_string()], 1)],
+            unmet.routes[0].before
+        );
+        assert_ne!(unmet.routes[0].before, unmet.routes[0].after);
+
+        let unchanged = &comparison.scenarios[1];
+        assert_eq!(0, unchanged.cost.delta());
+        assert!(unchanged.arc_loads.is_empty() && unchanged.routes.is_empty());
+
+        // the consistent flow on (a,b) is the minimum over all scenarios
+        assert_eq!(1, comparison.consistent_flows.len());
+        assert_eq!(1, comparison.consistent_flows[0].delta());
+    }
+
+    #[test]
+    fn test_compare_to() {
+        let network = network();
+        let comparison = network.compare_to(&network).unwrap();
+        assert!(comparison.consistent_flows.is_empty());
+        assert!(comparison
+            .scenarios
+            .iter()
+            .all(|scenario| scenario.arc_loads.is_empty() && scenario.routes.is_empty()));
+
+        let mut other = network.clone();
+        other.solutions.as_mut().unwrap().pop();
+        assert!(matches!(
+            network.compare_to(&other),
+            Err(SolverError::NetworkShapeError(_))
+        ));
+        other.solutions = None;
+        assert!(matches!(
+            network.compare_to(&other),
+            Err(SolverError::SkippedSolveError)
+        ));
+        other.baseline = None;
+        assert!(matches!(
+            other.compare_to_baseline(),
+            Err(SolverError::SkippedBaselineError)
+        ));
+    }
+
+    #[test]
+    fn test_export() {
+        let comparison = network().compare_to_baseline().unwrap();
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_comparison_{}.csv", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        comparison.export(filename).unwrap();
+        let csv = std::fs::read_to_string(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+
+        let kinds = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "cost",
+                "robustness_coefficient",
+                "consistent_flow",
+                "scenario_cost",
+                "arc_load",
+                "arc_load",
+                "route",
+                "scenario_cost",
+            ],
+            kinds
+        );
+        assert!(csv.contains("route,0,a,c,a->c: 1,a->b->c: 1"));
+    }
+}
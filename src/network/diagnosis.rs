@@ -0,0 +1,278 @@
+use std::fmt::Display;
+
+use crate::{
+    algorithms::{cut_arcs, max_flow},
+    Matrix, SolverError,
+};
+
+use super::Network;
+
+/// Demand which cannot cross a cut through the network, either of a single OD pair or of all
+/// origins towards one destination.
+#[derive(Debug, Clone)]
+pub struct Bottleneck {
+    pub origins: Vec<String>,
+    pub destination: String,
+    pub demand: usize,
+    pub max_flow: usize,
+    /// Vertices on the origin side of the minimum cut
+    pub source_side: Vec<String>,
+    /// Saturated arcs crossing the minimum cut
+    pub cut_arcs: Vec<(String, String)>,
+}
+
+impl Bottleneck {
+    /// Total capacity which has to be added to the cut arcs for the demand to fit through.
+    pub fn capacity_increase(&self) -> usize {
+        self.demand - self.max_flow
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InfeasibilityDiagnosis {
+    pub scenario: usize,
+    pub bottlenecks: Vec<Bottleneck>,
+}
+
+impl Display for InfeasibilityDiagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bottlenecks.is_empty() {
+            return write!(
+                f,
+                "Scenario {} could not be routed, although no OD pair is cut off by itself. The demand only conflicts in combination, e.g. on shared arcs, at vertices with limited capacity, or due to the intermediate arc sets.",
+                self.scenario
+            );
+        }
+        let mut string_repr = vec![format!(
+            "Scenario {} cannot be routed due to the following bottlenecks:",
+            self.scenario
+        )];
+        for bottleneck in self.bottlenecks.iter() {
+            string_repr.push(format!(
+                "  demand of {} from {} to {}, but at most {} units fit through the cut separating {{{}}} from the rest of the network. Its saturated arcs are {}, whose capacity needs to increase by {} in total.",
+                bottleneck.demand,
+                bottleneck.origins.join(", "),
+                bottleneck.destination,
+                bottleneck.max_flow,
+                bottleneck.source_side.join(", "),
+                bottleneck
+                    .cut_arcs
+                    .iter()
+                    .map(|(s, t)| format!("({s}->{t})"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                bottleneck.capacity_increase(),
+            ));
+        }
+        write!(f, "{}", string_repr.join("\n"))
+    }
+}
+
+impl Network {
+    /// Finds the OD pairs of scenario `lambda` whose demand exceeds the capacity of a minimum cut.
+    /// The demand towards each destination is checked as a whole first, and only if it does not
+    /// fit are its OD pairs checked one by one. Vertex capacities are ignored, and arc groups
+    /// only limit their individual arcs, so any bottleneck found is a proof of infeasibility.
+    pub fn diagnose_infeasibility(&self, lambda: usize) -> InfeasibilityDiagnosis {
+        let balance = &self.balances[lambda];
+        let len = self.vertices.len();
+        let mut bottlenecks = Vec::new();
+
+        for t in 0..len {
+            let demands = (0..len).map(|s| *balance.get(s, t)).collect::<Vec<_>>();
+            let demand = demands.iter().sum::<usize>();
+            if demand == 0 {
+                continue;
+            }
+
+            // a super source supplies every origin with its demand towards t
+            let mut capacities = self.commodity_capacities(lambda, |_, a_1| a_1 == t);
+            capacities.extend(&demands, &vec![0; len + 1]);
+            let (flow, mut source_side) = max_flow(&capacities, len, t, demand);
+            if flow == demand {
+                continue;
+            }
+            source_side.truncate(len);
+            capacities.shrink(1);
+
+            let origins = (0..len).filter(|s| demands[*s] > 0).collect::<Vec<_>>();
+            let mut od_bottlenecks = Vec::new();
+            for &s in origins.iter() {
+                let capacities = self.commodity_capacities(lambda, |a_0, a_1| (a_0, a_1) == (s, t));
+                let (flow, source_side) = max_flow(&capacities, s, t, demands[s]);
+                if flow < demands[s] {
+                    od_bottlenecks.push(self.bottleneck(
+                        &capacities,
+                        vec![s],
+                        t,
+                        demands[s],
+                        flow,
+                        &source_side,
+                    ));
+                }
+            }
+
+            // the OD pairs may only be cut off in combination
+            if od_bottlenecks.is_empty() {
+                od_bottlenecks.push(self.bottleneck(
+                    &capacities,
+                    origins,
+                    t,
+                    demand,
+                    flow,
+                    &source_side,
+                ));
+            }
+            bottlenecks.append(&mut od_bottlenecks);
+        }
+
+        InfeasibilityDiagnosis {
+            scenario: lambda,
+            bottlenecks,
+        }
+    }
+
+    /// Replaces errors about infeasible scenarios with a diagnosis of why they are infeasible.
+    pub(crate) fn explain_infeasibility(&self, e: SolverError) -> SolverError {
+        match e {
+            SolverError::NoFeasibleFlowError(lambda) | SolverError::GurobiSolutionError(lambda)
+                if lambda < self.balances.len() =>
+            {
+                SolverError::InfeasibleScenarioError(self.diagnose_infeasibility(lambda))
+            }
+            e => e,
+        }
+    }
+
+    /// Capacities of scenario `lambda` as a single commodity sees them: lifted fixed arcs are
+    /// unlimited, and only penalty arcs for which `own_penalty_arc` holds may be used.
+    fn commodity_capacities(
+        &self,
+        lambda: usize,
+        own_penalty_arc: impl Fn(usize, usize) -> bool,
+    ) -> Matrix<usize> {
+        let mut capacities = self.scenario_capacities(lambda);
+        for &(a_0, a_1) in self.fixed_arcs.iter() {
+            if self.is_lifted(lambda, a_0, a_1) {
+                capacities.set(a_0, a_1, usize::MAX);
+            }
+        }
+        for group in self.scenario_arc_groups(lambda, true, None) {
+            for &(a_0, a_1) in group.arcs.iter() {
+                if *capacities.get(a_0, a_1) > group.capacity {
+                    capacities.set(a_0, a_1, group.capacity);
+                }
+            }
+        }
+        for &(a_0, a_1) in self.penalty_arcs.iter() {
+            if !own_penalty_arc(a_0, a_1) {
+                capacities.set(a_0, a_1, 0);
+            }
+        }
+        capacities
+    }
+
+    fn bottleneck(
+        &self,
+        capacities: &Matrix<usize>,
+        origins: Vec<usize>,
+        t: usize,
+        demand: usize,
+        flow: usize,
+        source_side: &[bool],
+    ) -> Bottleneck {
+        let name = |v: usize| self.vertices[v].to_string();
+        Bottleneck {
+            origins: origins.into_iter().map(name).collect(),
+            destination: name(t),
+            demand,
+            max_flow: flow,
+            source_side: (0..self.vertices.len())
+                .filter(|v| source_side[*v])
+                .map(name)
+                .collect(),
+            cut_arcs: cut_arcs(capacities, source_side)
+                .into_iter()
+                .map(|(s, t)| (name(s), name(t)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A path from a over b to c, on which (b,c) is the bottleneck.
+    fn network() -> Network {
+        serde_json::from_value(json!({
+            "vertices": [
+                { "name": "a", "x": 0.0, "y": 0.0 },
+                { "name": "b", "x": 1.0, "y": 0.0 },
+                { "name": "c", "x": 2.0, "y": 0.0 },
+            ],
+            "capacities": [[0, 5, 0], [0, 0, 2], [1, 0, 0]],
+            "costs": [[0, 1, 0], [0, 0, 1], [1, 0, 0]],
+            "balances": [
+                [[0, 0, 3], [0, 0, 0], [0, 0, 0]],
+                [[0, 0, 2], [0, 0, 1], [0, 0, 0]],
+                [[0, 0, 1], [0, 0, 0], [0, 0, 0]],
+            ],
+            "fixed_arcs": [],
+            "baseline": null,
+            "solutions": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_od_pair_bottleneck() {
+        let diagnosis = network().diagnose_infeasibility(0);
+        assert_eq!(1, diagnosis.bottlenecks.len());
+        let bottleneck = &diagnosis.bottlenecks[0];
+        assert_eq!(vec!["a".to_string()], bottleneck.origins);
+        assert_eq!("c", bottleneck.destination);
+        assert_eq!((3, 2), (bottleneck.demand, bottleneck.max_flow));
+        assert_eq!(
+            vec!["a".to_string(), "b".to_string()],
+            bottleneck.source_side
+        );
+        assert_eq!(
+            vec![("b".to_string(), "c".to_string())],
+            bottleneck.cut_arcs
+        );
+        assert_eq!(1, bottleneck.capacity_increase());
+    }
+
+    #[test]
+    fn test_combined_bottleneck() {
+        // either OD pair fits through (b,c) by itself, but not both together
+        let diagnosis = network().diagnose_infeasibility(1);
+        assert_eq!(1, diagnosis.bottlenecks.len());
+        let bottleneck = &diagnosis.bottlenecks[0];
+        assert_eq!(vec!["a".to_string(), "b".to_string()], bottleneck.origins);
+        assert_eq!((3, 2), (bottleneck.demand, bottleneck.max_flow));
+        assert_eq!(
+            vec![("b".to_string(), "c".to_string())],
+            bottleneck.cut_arcs
+        );
+        assert!(diagnosis.to_string().contains("needs to increase by 1"));
+    }
+
+    #[test]
+    fn test_explain_infeasibility() {
+        let network = network();
+        assert!(network.diagnose_infeasibility(2).bottlenecks.is_empty());
+        assert!(matches!(
+            network.explain_infeasibility(SolverError::NoFeasibleFlowError(0)),
+            SolverError::InfeasibleScenarioError(InfeasibilityDiagnosis { scenario: 0, .. })
+        ));
+        // scenarios which do not exist are left alone
+        assert!(matches!(
+            network.explain_infeasibility(SolverError::GurobiSolutionError(3)),
+            SolverError::GurobiSolutionError(3)
+        ));
+    }
+}
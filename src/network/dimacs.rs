@@ -0,0 +1,166 @@
+//! Export of single scenarios as DIMACS min-cost flow instances, e.g. for LEMON or CS2, and import
+//! of the flow solutions these codes produce. Vertices are numbered from 1 in the order of the
+//! network, and all values are given in quanta, since the format only allows integers.
+//!
+//! Multi-commodity instances extend the format by one line `k <origin> <destination> <amount>`
+//! per OD pair in place of the `n` lines, and state the number of commodities as the last field
+//! of the problem line.
+
+use crate::{Matrix, Result, SolverError};
+
+use super::{Network, ScenarioSolution};
+
+fn error(message: impl std::fmt::Display) -> SolverError {
+    SolverError::DimacsFormatError(message.to_string())
+}
+
+impl Network {
+    /// Writes scenario `lambda` as a DIMACS min-cost flow instance, in which the supply and
+    /// demand of every vertex are the sums over its OD pairs. With `multi_commodity`, each OD
+    /// pair instead becomes a commodity of its own. Lifted fixed arcs may carry the entire
+    /// supply of the scenario. Arc groups and vertex capacities cannot be expressed, and are
+    /// left out with a warning.
+    pub fn to_dimacs(&self, filename: &str, lambda: usize, multi_commodity: bool) -> Result<()> {
+        let Some(balance) = self.balances.get(lambda) else {
+            return Err(SolverError::NetworkShapeError(format!(
+                "Attempted to export scenario {lambda}, but only {} scenarios exist.",
+                self.balances.len()
+            )));
+        };
+        if !self.arc_groups.is_empty() || self.vertices.iter().any(|v| v.capacity.is_some()) {
+            log::warn!("Arc groups and vertex capacities are not part of the DIMACS instance.");
+        }
+        let total_supply = balance.sum();
+        let capacities = self.scenario_capacities(lambda);
+        let arcs = capacities
+            .indices()
+            .filter(|&(s, t)| s != t && (*capacities.get(s, t) > 0 || self.is_lifted(lambda, s, t)))
+            .map(|(s, t)| match self.is_lifted(lambda, s, t) {
+                true => (s, t, total_supply),
+                false => (s, t, *capacities.get(s, t)),
+            })
+            .collect::<Vec<_>>();
+        let commodities = balance
+            .indices()
+            .filter(|&(s, t)| s != t && *balance.get(s, t) > 0)
+            .collect::<Vec<_>>();
+
+        let mut dimacs = vec![format!(
+            "c scenario {lambda} of a network with {} vertices",
+            self.vertices.len()
+        )];
+        match multi_commodity {
+            true => {
+                dimacs.push(format!(
+                    "p min {} {} {}",
+                    self.vertices.len(),
+                    arcs.len(),
+                    commodities.len()
+                ));
+                for &(s, t) in commodities.iter() {
+                    dimacs.push(format!("k {} {} {}", s + 1, t + 1, balance.get(s, t)));
+                }
+            }
+            false => {
+                dimacs.push(format!("p min {} {}", self.vertices.len(), arcs.len()));
+                for v in 0..self.vertices.len() {
+                    let supply = (0..self.vertices.len())
+                        .filter(|&u| u != v)
+                        .map(|u| *balance.get(v, u) as i64 - *balance.get(u, v) as i64)
+                        .sum::<i64>();
+                    if supply != 0 {
+                        dimacs.push(format!("n {} {supply}", v + 1));
+                    }
+                }
+            }
+        }
+        for (s, t, capacity) in arcs {
+            dimacs.push(format!(
+                "a {} {} 0 {capacity} {}",
+                s + 1,
+                t + 1,
+                self.costs.get(s, t)
+            ));
+        }
+
+        let dimacs_str = dimacs.join("\n") + "\n";
+        log::debug!("Writing\n{dimacs_str}\nto {filename}");
+        std::fs::write(filename, dimacs_str)?;
+        Ok(())
+    }
+
+    /// Reads a DIMACS flow solution of scenario `lambda` and uses its `f` lines as the arc loads
+    /// of the scenario, replacing any previous solution of it. Flows listed more than once, e.g.
+    /// once per commodity, are added up. As the solution does not tell which OD pairs were
+    /// served, all supply is assumed to be routed.
+    pub fn add_dimacs_solution(&mut self, filename: &str, lambda: usize) -> Result<()> {
+        let len = self.vertices.len();
+        let Some(balance) = self.balances.get(lambda) else {
+            return Err(SolverError::NetworkShapeError(format!(
+                "Attempted to add a solution of scenario {lambda}, but only {} scenarios exist.",
+                self.balances.len()
+            )));
+        };
+        let mut solution = ScenarioSolution::new(lambda, balance);
+        solution.supply_remaining = Matrix::filled_with(0, len, len);
+        solution.arc_loads = parse_arc_loads(&std::fs::read_to_string(filename)?, len)?;
+
+        let solutions = self.solutions.get_or_insert_with(Vec::new);
+        solutions.retain(|solution| solution.id != lambda);
+        solutions.push(solution);
+        solutions.sort_by_key(|solution| solution.id);
+        let slacks = self.recompute_slack(self.solutions.as_deref().unwrap_or_default());
+        for (solution, slack) in self.solutions.iter_mut().flatten().zip(slacks) {
+            solution.slack = slack;
+        }
+        Ok(())
+    }
+}
+
+/// Sums up the flows of the `f` lines of a solution into arc loads.
+fn parse_arc_loads(solution: &str, len: usize) -> Result<Matrix<usize>> {
+    let mut arc_loads = Matrix::filled_with(0, len, len);
+    for (i, line) in solution.lines().enumerate() {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.first() != Some(&"f") {
+            continue;
+        }
+        let [_, s, t, flow] = fields[..] else {
+            return Err(error(format!(
+                "line {} is not of the form \"f u v flow\"",
+                i + 1
+            )));
+        };
+        let vertex = |v: &str| match v.parse::<usize>() {
+            Ok(v) if (1..=len).contains(&v) => Ok(v - 1),
+            _ => Err(error(format!(
+                "line {} refers to an unknown vertex {v}",
+                i + 1
+            ))),
+        };
+        let (s, t) = (vertex(s)?, vertex(t)?);
+        let flow = flow
+            .parse::<usize>()
+            .map_err(|_| error(format!("line {} has an invalid flow {flow}", i + 1)))?;
+        let load = *arc_loads.get(s, t) + flow;
+        arc_loads.set(s, t, load);
+    }
+    Ok(arc_loads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arc_loads() {
+        let solution = "c solution\ns 10\nf 1 2 3\nf 1 2 1\nf 3 1 2\n";
+        let arc_loads = parse_arc_loads(solution, 3).unwrap();
+
+        assert_eq!(4, *arc_loads.get(0, 1));
+        assert_eq!(2, *arc_loads.get(2, 0));
+        assert_eq!(6, arc_loads.sum());
+        assert!(parse_arc_loads("f 1 4 1", 3).is_err());
+        assert!(parse_arc_loads("f 1 2", 3).is_err());
+    }
+}
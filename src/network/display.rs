@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use colored::Color;
 
-use crate::network::{Network, Solution};
+use crate::network::{Network, ScenarioSolution, Solution};
 
 impl Network {
     fn display_solutions(&self) -> String {
@@ -10,21 +10,57 @@ impl Network {
             return "No solutions have been found yet.".to_string();
         };
         format!(
-            "The following arc loads constitute the solution:\n{}\nThe network cost is {}.",
+            "The following arc loads constitute the solution:\n{}\nThe network cost is {}{}.",
             solutions.iter()
                 .map(|solution| format!(
-                    "Scenario {}, with cost {} and {} slack used (target: ≤ {}) in delivery of {}/{} supply units:\n{}",
+                    "Scenario {}, with cost {}{} and {} slack used (target: ≤ {}) in delivery of {}/{} supply units:\n{}",
                     solution.id,
-                    solution.cost(&self.costs),
+                    solution.cost(self.routing_costs()),
+                    self.display_metric_costs(&solution.metric_costs(&self.metrics())),
                     solution.slack,
                     self.options.slack_fn.apply(&self.balances)[solution.id],
                     solution.supply_delivered(self.balances[solution.id].sum()),
                     self.balances[solution.id].sum(),
                     solution.arc_loads.highlight(&self.fixed_arcs, colored::Color::Blue),
-                ))
+                ) + &self.display_unmet_demand(solution))
                 .collect::<Vec<String>>()
                 .join("\n"),
-            solutions.cost(&self.costs, &self.options.cost_fn)
+            solutions.cost(self.routing_costs(), &self.options.cost_fn),
+            self.display_metric_costs(&self.metric_costs().unwrap_or_default()),
+        )
+    }
+
+    fn display_unmet_demand(&self, solution: &ScenarioSolution) -> String {
+        let unmet_demand = solution.unmet_demand_pairs();
+        if unmet_demand.is_empty() {
+            return String::new();
+        }
+        format!(
+            "\n{} supply units of demand are unmet: {}",
+            solution.unmet_demand.sum(),
+            unmet_demand
+                .iter()
+                .map(|(s, t, unmet)| format!(
+                    "({}->{}): {}",
+                    self.vertices[*s], self.vertices[*t], unmet
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    /// Lists the cost in every metric, unless the network only has the primary one.
+    fn display_metric_costs(&self, metric_costs: &[(String, usize)]) -> String {
+        if self.cost_metrics.is_empty() {
+            return String::new();
+        }
+        format!(
+            " ({})",
+            metric_costs
+                .iter()
+                .map(|(name, cost)| format!("{name}: {cost}"))
+                .collect::<Vec<_>>()
+                .join(", ")
         )
     }
 }
@@ -42,11 +78,33 @@ impl Display for Network {
                 .collect::<Vec<_>>()
                 .join(", ")
         ));
+        if self.quantum != 1.0 || self.cost_quantum != 1.0 {
+            string_repr.push(format!(
+                "All supply-like values are given in quanta of {}, all costs in quanta of {}.",
+                self.quantum, self.cost_quantum
+            ));
+        }
         string_repr.push(format!("Capacities:\n{}", self.capacities));
         string_repr.push(format!("Costs:\n{}\n", self.costs));
+        for (name, metric) in self.cost_metrics.iter() {
+            string_repr.push(format!("Costs ({name}):\n{metric}\n"));
+        }
         string_repr.push(format!("{} Scenarios:", self.balances.len()));
         self.balances.iter().enumerate().for_each(|(i, b)| {
             string_repr.push(format!("{}.:\n{}", i, b));
+            if self.has_capacity_overrides(i) {
+                string_repr.push(format!(
+                    "Capacity overrides: {}",
+                    self.capacity_overrides[i]
+                        .iter()
+                        .map(|(s, t, c)| format!(
+                            "({}->{}): {}",
+                            self.vertices[*s], self.vertices[*t], c
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ));
+            }
         });
         string_repr.push("".to_string());
         string_repr.push(format!(
@@ -57,11 +115,43 @@ impl Display for Network {
                 .collect::<Vec<String>>()
                 .join(", ")
         ));
+        for group in self.arc_groups.iter() {
+            string_repr.push(format!(
+                "The following arcs share a capacity of {}: {}",
+                group.capacity,
+                group
+                    .arcs
+                    .iter()
+                    .map(|(s, t)| format!("({}->{})", self.vertices[*s], self.vertices[*t]))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        if !self.penalty_arcs.is_empty() {
+            string_repr.push(format!(
+                "The following arcs are penalty arcs: {}",
+                self.penalty_arcs
+                    .iter()
+                    .map(|(s, t)| format!("({}->{})", self.vertices[*s], self.vertices[*t]))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        if self.vertices.iter().any(|v| v.capacity.is_some()) {
+            string_repr.push(format!(
+                "The following vertices have a limited capacity: {}",
+                self.vertices
+                    .iter()
+                    .filter_map(|v| v.capacity.map(|c| format!("{}: {}", v, c)))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
         string_repr.push("".to_string());
         if let Some(baseline) = &self.baseline {
             string_repr.push(
                     format!("The lower bound on network cost is {}. Omitting consistent flow constraints yields the following consistent flows:\n{}",
-                    baseline.cost(&self.costs, &self.options.cost_fn),
+                    baseline.cost(self.routing_costs(), &self.options.cost_fn),
                     baseline.consistent_flows_colorized(&self.fixed_arcs, Color::Blue))
                 );
             string_repr.push("".to_string());
@@ -74,8 +164,8 @@ impl Display for Network {
             string_repr.push("".to_string());
             string_repr.push(format!(
                 "This corresponds to a relative change in cost of {} and the following relative changes in consistent flows:\n{}",
-                (solutions.cost(&self.costs, &self.options.cost_fn) as i64)
-                    - (baseline.cost(&self.costs, &self.options.cost_fn) as i64),
+                (solutions.cost(self.routing_costs(), &self.options.cost_fn) as i64)
+                    - (baseline.cost(self.routing_costs(), &self.options.cost_fn) as i64),
                 solutions.highlight_difference_to(baseline, &self.fixed_arcs)
             ));
             string_repr.push("".to_string());
@@ -110,7 +200,7 @@ impl Display for Network {
             ));
             string_repr.push(format!(
                 "              cost: {}",
-                *self.costs.get(fixed_arc.0, fixed_arc.1)
+                *self.routing_costs().get(fixed_arc.0, fixed_arc.1)
             ));
             string_repr.push(format!(
                 "          capacity: {}",
@@ -154,8 +244,8 @@ impl Display for Network {
             if let Some(baseline) = &self.baseline {
                 string_repr.push(format!(
                     "               Benefit of the solution: κ = {}",
-                    (baseline.cost(&self.costs, &self.options.cost_fn) as i64)
-                        - (solutions.cost(&self.costs, &self.options.cost_fn) as i64),
+                    (baseline.cost(self.routing_costs(), &self.options.cost_fn) as i64)
+                        - (solutions.cost(self.routing_costs(), &self.options.cost_fn) as i64),
                 ));
             }
         }
@@ -0,0 +1,64 @@
+use crate::Result;
+
+use super::Network;
+
+/// Quotes a string as a DOT identifier.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Network {
+    /// Writes the network as a Graphviz DOT digraph. Vertices are pinned to their positions for
+    /// `neato`, and arcs are labelled with their cost and capacity. All vertex and arc attributes
+    /// are included as attributes of their own, as are the arc loads of every scenario of the
+    /// solution as "load_λ" with `loads`.
+    pub fn to_dot(&self, filename: &str, loads: bool) -> Result<()> {
+        let solutions = match loads {
+            true => self.sorted_solutions()?,
+            false => vec![],
+        };
+        let mut dot = vec![
+            "digraph network {".to_string(),
+            "  node [shape=circle];".to_string(),
+        ];
+        for (v, vertex) in self.vertices.iter().enumerate() {
+            dot.push(format!(
+                "  v{v} [label={}, pos=\"{},{}!\", is_station={}{}];",
+                quote(&vertex.name),
+                vertex.x,
+                vertex.y,
+                vertex.is_station,
+                if vertex.is_station {
+                    ", style=filled"
+                } else {
+                    ""
+                },
+            ));
+        }
+        for (s, t) in self.arcs() {
+            let capacity = *self.capacities.get(s, t) as f64 * self.quantum;
+            let cost = *self.costs.get(s, t) as f64 * self.cost_quantum;
+            let fixed = self.fixed_arcs.contains(&(s, t));
+            let mut attributes = vec![
+                format!("label=\"{cost}/{capacity}\""),
+                format!("capacity={capacity}"),
+                format!("cost={cost}"),
+                format!("fixed={fixed}"),
+            ];
+            if fixed {
+                attributes.push("color=teal".to_string());
+            }
+            for solution in solutions.iter() {
+                let load = *solution.arc_loads.get(s, t) as f64 * self.quantum;
+                attributes.push(format!("load_{}={load}", solution.id));
+            }
+            dot.push(format!("  v{s} -> v{t} [{}];", attributes.join(", ")));
+        }
+        dot.push("}".to_string());
+
+        let dot_str = dot.join("\n");
+        log::debug!("Writing\n{dot_str}\nto {filename}");
+        std::fs::write(filename, dot_str)?;
+        Ok(())
+    }
+}
@@ -43,6 +43,13 @@ pub(super) struct NetworkData {
 
     time_preprocess: Option<usize>,
     time_solve: Option<usize>,
+
+    // Columns added later on come last, so that rows can still be appended to older exports
+    metric_costs: String,
+    unmet_demand_total: usize,
+    unmet_demand: String,
+    quantum: f64,
+    cost_quantum: f64,
 }
 
 impl NetworkData {
@@ -95,8 +102,8 @@ impl NetworkData {
             },
             benefit: match (&network.solutions, &network.baseline) {
                 (Some(solutions), Some(baseline)) => {
-                    (baseline.cost(&network.costs, &network.options.cost_fn) as i64)
-                        - (solutions.cost(&network.costs, &network.options.cost_fn) as i64)
+                    (baseline.cost(network.routing_costs(), &network.options.cost_fn) as i64)
+                        - (solutions.cost(network.routing_costs(), &network.options.cost_fn) as i64)
                 }
                 _ => 0,
             },
@@ -188,26 +195,26 @@ impl NetworkData {
             lower_bound_max: network
                 .baseline
                 .as_ref()
-                .map(|baseline| baseline.cost(&network.costs, &CostFunction::Max)),
+                .map(|baseline| baseline.cost(network.routing_costs(), &CostFunction::Max)),
             lower_bound_mean: network
                 .baseline
                 .as_ref()
-                .map(|baseline| baseline.cost(&network.costs, &CostFunction::Mean)),
+                .map(|baseline| baseline.cost(network.routing_costs(), &CostFunction::Mean)),
             lower_bound_median: network
                 .baseline
                 .as_ref()
-                .map(|baseline| baseline.cost(&network.costs, &CostFunction::Median)),
+                .map(|baseline| baseline.cost(network.routing_costs(), &CostFunction::Median)),
 
             cost_max: match &network.solutions {
-                Some(solutions) => solutions.cost(&network.costs, &CostFunction::Max),
+                Some(solutions) => solutions.cost(network.routing_costs(), &CostFunction::Max),
                 None => 0,
             },
             cost_mean: match &network.solutions {
-                Some(solutions) => solutions.cost(&network.costs, &CostFunction::Mean),
+                Some(solutions) => solutions.cost(network.routing_costs(), &CostFunction::Mean),
                 None => 0,
             },
             cost_median: match &network.solutions {
-                Some(solutions) => solutions.cost(&network.costs, &CostFunction::Median),
+                Some(solutions) => solutions.cost(network.routing_costs(), &CostFunction::Median),
                 None => 0,
             },
 
@@ -217,6 +224,75 @@ impl NetworkData {
 
             time_preprocess,
             time_solve,
+
+            metric_costs: network
+                .metric_costs()
+                .unwrap_or_default()
+                .iter()
+                .map(|(name, cost)| format!("{name}={cost}"))
+                .collect::<Vec<_>>()
+                .join(";"),
+            unmet_demand_total: network
+                .solutions
+                .iter()
+                .flatten()
+                .map(|s| s.unmet_demand.sum())
+                .sum(),
+            unmet_demand: network
+                .solutions
+                .iter()
+                .flatten()
+                .flat_map(|solution| {
+                    solution
+                        .unmet_demand_pairs()
+                        .into_iter()
+                        .map(|(s, t, unmet)| {
+                            format!(
+                                "{}:{}->{}={}",
+                                solution.id, network.vertices[s], network.vertices[t], unmet
+                            )
+                        })
+                })
+                .collect::<Vec<_>>()
+                .join(";"),
+            quantum: network.quantum,
+            cost_quantum: network.cost_quantum,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    #[test]
+    fn test_metric_columns() {
+        let mut network = solved_network();
+        network.cost_metrics.insert(
+            "km".to_string(),
+            Matrix::from_elements(&[0, 5, 0, 0, 0, 2, 1, 1, 0], 3, 3),
+        );
+        let solutions = network.solutions.as_mut().unwrap();
+        let solution = solutions
+            .iter_mut()
+            .find(|solution| solution.id == 1)
+            .unwrap();
+        solution.unmet_demand.set(0, 2, 1);
+
+        let data = NetworkData::from_network(&network, None, Some(7));
+        assert_eq!("costs=4;km=14", data.metric_costs);
+        assert_eq!(1, data.unmet_demand_total);
+        assert_eq!(Some(7), data.time_solve);
+        assert_eq!(1.0, data.quantum);
+
+        // the new columns come last, after the older ones
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(&data).unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let header = csv.lines().next().unwrap();
+        assert!(header.ends_with(
+            "time_solve,metric_costs,unmet_demand_total,unmet_demand,quantum,cost_quantum"
+        ));
+    }
+}
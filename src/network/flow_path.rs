@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// A path taken by some of the supply of an OD pair.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FlowPath {
+    pub origin: usize,
+    pub destination: usize,
+    pub vertices: Vec<usize>,
+    pub amount: usize,
+}
+
+impl FlowPath {
+    pub fn arcs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.vertices.windows(2).map(|arc| (arc[0], arc[1]))
+    }
+
+    pub fn uses_arc(&self, s: usize, t: usize) -> bool {
+        self.arcs().any(|arc| arc == (s, t))
+    }
+}
+
+/// Adds `amount` to the path of the OD pair `(origin, destination)` via `vertices`.
+pub(crate) fn record_path(
+    paths: &mut Vec<FlowPath>,
+    (origin, destination): (usize, usize),
+    vertices: &[usize],
+    amount: usize,
+) {
+    match paths.iter_mut().find(|path| {
+        (path.origin, path.destination) == (origin, destination) && path.vertices == vertices
+    }) {
+        Some(path) => path.amount += amount,
+        None => paths.push(FlowPath {
+            origin,
+            destination,
+            vertices: vertices.to_vec(),
+            amount,
+        }),
+    }
+}
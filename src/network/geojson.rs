@@ -0,0 +1,177 @@
+//! GeoJSON export, e.g. for QGIS. Vertices become points and arcs line strings between them, with
+//! their attributes as properties in the units of network files.
+
+use serde_json::{json, Map, Value};
+
+use crate::Result;
+
+use super::{Network, Projection, Solution};
+
+impl Network {
+    /// Writes the network as a GeoJSON feature collection of stations and junctions as points
+    /// and arcs as line strings. Positions of networks imported from geographic data are
+    /// converted back and written as WGS 84 coordinates. Other positions are used as they are,
+    /// under the named `crs` if given, or, with an `origin` as "latitude, longitude", taken as
+    /// kilometres east and north of it. Solved networks also get the load of every scenario as
+    /// "load_λ", and their fixed arcs the consistent flow and consistency of the solution.
+    pub fn to_geojson(
+        &self,
+        filename: &str,
+        crs: Option<&str>,
+        origin: Option<(f64, f64)>,
+    ) -> Result<()> {
+        let projection = match (origin, crs) {
+            (Some(origin), _) => Some(Projection::at_origin(origin)),
+            (None, Some(_)) => None,
+            (None, None) => self.projection,
+        };
+        let coordinates = |v: usize| {
+            let (x, y) = (self.vertices[v].x as f64, self.vertices[v].y as f64);
+            match projection {
+                Some(projection) => {
+                    let (lat, lon) = projection.unproject((x, y));
+                    json!([lon, lat])
+                }
+                None => json!([x, y]),
+            }
+        };
+        let solutions = self.sorted_solutions().unwrap_or_default();
+        let consistent_flows = self
+            .solutions
+            .as_deref()
+            .filter(|solutions| !solutions.is_empty())
+            .map(|solutions| (solutions, solutions.consistent_flows(&self.fixed_arcs)));
+
+        let mut features = Vec::new();
+        for (v, vertex) in self.vertices.iter().enumerate() {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": coordinates(v) },
+                "properties": {
+                    "kind": if vertex.is_station { "station" } else { "junction" },
+                    "name": vertex.name,
+                    "capacity": vertex.capacity.map(|capacity| capacity as f64 * self.quantum),
+                },
+            }));
+        }
+        for (s, t) in self.arcs() {
+            let fixed = self.fixed_arcs.contains(&(s, t));
+            let mut properties = Map::new();
+            properties.insert("kind".to_string(), json!("arc"));
+            properties.insert("from".to_string(), json!(self.vertices[s].name));
+            properties.insert("to".to_string(), json!(self.vertices[t].name));
+            properties.insert(
+                "capacity".to_string(),
+                json!(*self.capacities.get(s, t) as f64 * self.quantum),
+            );
+            properties.insert(
+                "cost".to_string(),
+                json!(*self.costs.get(s, t) as f64 * self.cost_quantum),
+            );
+            properties.insert("fixed".to_string(), json!(fixed));
+            for solution in solutions.iter() {
+                properties.insert(
+                    format!("load_{}", solution.id),
+                    json!(*solution.arc_loads.get(s, t) as f64 * self.quantum),
+                );
+            }
+            if let Some((scenario_solutions, consistent_flows)) = &consistent_flows {
+                let (consistent_flow, consistency) = match fixed {
+                    true => (
+                        json!(*consistent_flows.get(s, t) as f64 * self.quantum),
+                        json!(scenario_solutions.consistency(&(s, t))),
+                    ),
+                    false => (Value::Null, Value::Null),
+                };
+                properties.insert("consistent_flow".to_string(), consistent_flow);
+                properties.insert("consistency".to_string(), consistency);
+            }
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [coordinates(s), coordinates(t)],
+                },
+                "properties": properties,
+            }));
+        }
+
+        let mut geojson = json!({
+            "type": "FeatureCollection",
+            "features": features,
+        });
+        if let (Some(crs), None) = (crs, projection) {
+            geojson["crs"] = json!({ "type": "name", "properties": { "name": crs } });
+        }
+
+        let geojson_str = serde_json::to_string_pretty(&geojson)?;
+        log::debug!("Writing\n{geojson_str}\nto {filename}");
+        std::fs::write(filename, geojson_str)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    #[test]
+    fn test_to_geojson() {
+        let mut network = solved_network();
+        network.quantum = 0.5;
+        network.projection = Some(Projection::at_origin((52.5, 13.4)));
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_geojson_{}.geojson", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        network.to_geojson(filename, None, None).unwrap();
+        let geojson: Value =
+            serde_json::from_str(&std::fs::read_to_string(filename).unwrap()).unwrap();
+        std::fs::remove_file(filename).unwrap();
+
+        assert!(geojson.get("crs").is_none());
+        let features = geojson["features"].as_array().unwrap();
+        let (points, lines): (Vec<_>, Vec<_>) = features
+            .iter()
+            .partition(|feature| feature["geometry"]["type"] == "Point");
+        assert_eq!(3, points.len());
+        assert_eq!(5, lines.len());
+        assert!(lines
+            .iter()
+            .all(|line| line["geometry"]["type"] == "LineString"));
+
+        // a sits at the origin, b 1 km east of it, both as longitude and latitude
+        let coordinates = |feature: &Value| {
+            let coordinates = feature["geometry"]["coordinates"].as_array().unwrap();
+            (
+                coordinates[0].as_f64().unwrap(),
+                coordinates[1].as_f64().unwrap(),
+            )
+        };
+        let (lon, lat) = coordinates(points[0]);
+        assert!((lon - 13.4).abs() < 1e-6 && (lat - 52.5).abs() < 1e-6);
+        let (lon, lat) = coordinates(points[1]);
+        assert!(lon > 13.41 && lon < 13.42 && (lat - 52.5).abs() < 1e-6);
+
+        let arc = |s: &str, t: &str| {
+            lines
+                .iter()
+                .find(|line| line["properties"]["from"] == s && line["properties"]["to"] == t)
+                .map(|line| &line["properties"])
+                .unwrap()
+        };
+        let fixed = arc("a", "b");
+        assert_eq!(json!(true), fixed["fixed"]);
+        assert_eq!(json!(0.5), fixed["load_0"]);
+        assert_eq!(json!(1.0), fixed["load_1"]);
+        assert_eq!(json!(0.5), fixed["consistent_flow"]);
+        let solutions = network.solutions.as_deref().unwrap();
+        assert_eq!(json!(solutions.consistency(&(0, 1))), fixed["consistency"]);
+
+        let unfixed = arc("c", "a");
+        assert_eq!(json!(false), unfixed["fixed"]);
+        assert_eq!(json!(0.0), unfixed["load_1"]);
+        assert_eq!(Value::Null, unfixed["consistent_flow"]);
+        assert_eq!(Value::Null, unfixed["consistency"]);
+    }
+}
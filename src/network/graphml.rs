@@ -0,0 +1,354 @@
+//! GraphML export and import, for tools such as yEd and Gephi. Vertex and arc attributes are
+//! stored as data, in the units of network files. Imported files only need vertices and arcs,
+//! and are matched by the names of their data keys, so that files written by other tools work
+//! as well.
+
+use std::{collections::HashMap, str::FromStr};
+
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::{Matrix, Options, Result, SolverError};
+
+use super::{Network, Vertex};
+
+/// Data keys as "id, domain, type". Their ids double as their names.
+const KEYS: [(&str, &str, &str); 9] = [
+    ("quantum", "graph", "double"),
+    ("cost_quantum", "graph", "double"),
+    ("name", "node", "string"),
+    ("x", "node", "double"),
+    ("y", "node", "double"),
+    ("is_station", "node", "boolean"),
+    ("capacity", "edge", "double"),
+    ("cost", "edge", "double"),
+    ("fixed", "edge", "boolean"),
+];
+
+impl Network {
+    /// Writes the network as GraphML. With `loads`, the arc loads of every scenario of the
+    /// solution are added to the arcs as data of "load_λ".
+    pub fn to_graphml(&self, filename: &str, loads: bool) -> Result<()> {
+        let solutions = match loads {
+            true => self.sorted_solutions()?,
+            false => vec![],
+        };
+        let data = |key: &str, value: &dyn std::fmt::Display| {
+            format!("<data key=\"{key}\">{}</data>", escape(value.to_string()))
+        };
+        let mut graphml = vec![
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">".to_string(),
+        ];
+        for (id, domain, kind) in KEYS {
+            graphml.push(format!(
+                "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{id}\" attr.type=\"{kind}\"/>"
+            ));
+        }
+        for solution in solutions.iter() {
+            graphml.push(format!(
+                "  <key id=\"load_{0}\" for=\"edge\" attr.name=\"load_{0}\" attr.type=\"double\"/>",
+                solution.id
+            ));
+        }
+
+        graphml.push("  <graph id=\"network\" edgedefault=\"directed\">".to_string());
+        graphml.push(format!("    {}", data("quantum", &self.quantum)));
+        graphml.push(format!("    {}", data("cost_quantum", &self.cost_quantum)));
+        for (v, vertex) in self.vertices.iter().enumerate() {
+            graphml.push(format!(
+                "    <node id=\"v{v}\">{}{}{}{}</node>",
+                data("name", &vertex.name),
+                data("x", &vertex.x),
+                data("y", &vertex.y),
+                data("is_station", &vertex.is_station),
+            ));
+        }
+        for (s, t) in self.arcs() {
+            let mut edge = format!(
+                "    <edge id=\"e{s}_{t}\" source=\"v{s}\" target=\"v{t}\">{}{}{}",
+                data(
+                    "capacity",
+                    &(*self.capacities.get(s, t) as f64 * self.quantum)
+                ),
+                data("cost", &(*self.costs.get(s, t) as f64 * self.cost_quantum)),
+                data("fixed", &self.fixed_arcs.contains(&(s, t))),
+            );
+            for solution in solutions.iter() {
+                let load = *solution.arc_loads.get(s, t) as f64 * self.quantum;
+                edge.push_str(&data(&format!("load_{}", solution.id), &load));
+            }
+            edge.push_str("</edge>");
+            graphml.push(edge);
+        }
+        graphml.push("  </graph>".to_string());
+        graphml.push("</graphml>".to_string());
+
+        let graphml_str = graphml.join("\n");
+        log::debug!("Writing\n{graphml_str}\nto {filename}");
+        std::fs::write(filename, graphml_str)?;
+        Ok(())
+    }
+
+    /// Reads a network from a GraphML file. Vertices take their name from data of "name" or
+    /// "label", and arcs their capacity and cost from data of the same names. The network has a
+    /// single scenario without any demand.
+    pub fn from_graphml(options: &Options, filename: &str) -> Result<Self> {
+        let graph = Graph::read(filename)?;
+        let quantum = graph.number("quantum", &graph.data)?.unwrap_or(1.0);
+        let cost_quantum = graph.number("cost_quantum", &graph.data)?.unwrap_or(1.0);
+
+        let mut indices = HashMap::new();
+        let mut vertices = Vec::new();
+        for (v, node) in graph.nodes.iter().enumerate() {
+            let id = node.attributes.get("id").cloned().unwrap_or_default();
+            let name = match graph.value("name", &node.data) {
+                Some(name) => name.to_string(),
+                None => graph.value("label", &node.data).unwrap_or(&id).to_string(),
+            };
+            vertices.push(Vertex {
+                name,
+                x: graph.number("x", &node.data)?.unwrap_or(0.0),
+                y: graph.number("y", &node.data)?.unwrap_or(0.0),
+                is_station: graph.value("is_station", &node.data).is_none_or(parse_bool),
+                capacity: None,
+            });
+            indices.insert(id, v);
+        }
+
+        let len = vertices.len();
+        let mut capacities = Matrix::filled_with(0, len, len);
+        let mut costs = Matrix::filled_with(0, len, len);
+        let mut fixed_arcs = Vec::new();
+        for edge in graph.edges.iter() {
+            let vertex = |key: &str| {
+                edge.attributes
+                    .get(key)
+                    .and_then(|id| indices.get(id))
+                    .copied()
+                    .ok_or_else(|| {
+                        SolverError::NetworkImportError(format!("an edge has an unknown {key}"))
+                    })
+            };
+            let (s, t) = (vertex("source")?, vertex("target")?);
+            let capacity: f64 = graph.number("capacity", &edge.data)?.unwrap_or(0.0);
+            let cost: f64 = graph.number("cost", &edge.data)?.unwrap_or(0.0);
+            let fixed = graph.value("fixed", &edge.data).is_some_and(parse_bool);
+            let directed = match edge.attributes.get("directed") {
+                Some(directed) => parse_bool(directed),
+                None => graph.directed,
+            };
+            let arcs = match directed {
+                true => vec![(s, t)],
+                false => vec![(s, t), (t, s)],
+            };
+            for (s, t) in arcs {
+                capacities.set(s, t, (capacity / quantum).round() as usize);
+                costs.set(s, t, (cost / cost_quantum).round() as usize);
+                if fixed && !fixed_arcs.contains(&(s, t)) {
+                    fixed_arcs.push((s, t));
+                }
+            }
+        }
+
+        Ok(Network {
+            vertices,
+            capacities,
+            costs,
+            cost_metrics: Default::default(),
+            balances: vec![Matrix::filled_with(0, len, len)],
+            fixed_arcs,
+            quantum,
+            cost_quantum,
+            capacity_overrides: vec![],
+            arc_groups: vec![],
+            penalty_arcs: vec![],
+            projection: None,
+            baseline: None,
+            solutions: None,
+            auxiliary_network: None,
+            options: options.clone(),
+        })
+    }
+}
+
+fn parse_bool(value: &str) -> bool {
+    matches!(value.trim(), "true" | "1")
+}
+
+/// A node or edge, with its XML attributes and data by key id.
+#[derive(Default)]
+struct GraphElement {
+    attributes: HashMap<String, String>,
+    data: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct Graph {
+    /// Names of the data keys by id
+    keys: HashMap<String, String>,
+    directed: bool,
+    data: HashMap<String, String>,
+    nodes: Vec<GraphElement>,
+    edges: Vec<GraphElement>,
+}
+
+impl Graph {
+    fn read(filename: &str) -> Result<Self> {
+        let mut graph = Graph {
+            directed: true,
+            ..Default::default()
+        };
+        let mut reader = Reader::from_file(filename)?;
+        let mut buf = Vec::new();
+        // the element currently read, and whether it is an edge
+        let mut current: Option<(GraphElement, bool)> = None;
+        let mut data: Option<(String, String)> = None;
+        loop {
+            let event = reader.read_event_into(&mut buf)?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    let empty = matches!(event, Event::Empty(_));
+                    match e.name().as_ref() {
+                        b"key" => {
+                            let attributes = attributes(e)?;
+                            if let Some(id) = attributes.get("id") {
+                                let name = attributes.get("attr.name").unwrap_or(id);
+                                graph.keys.insert(id.clone(), name.clone());
+                            }
+                        }
+                        b"graph" => {
+                            graph.directed = attributes(e)?
+                                .get("edgedefault")
+                                .is_none_or(|edgedefault| edgedefault != "undirected");
+                        }
+                        name @ (b"node" | b"edge") => {
+                            let element = GraphElement {
+                                attributes: attributes(e)?,
+                                data: HashMap::new(),
+                            };
+                            current = Some((element, name == b"edge"));
+                            if empty {
+                                graph.push(current.take());
+                            }
+                        }
+                        b"data" if !empty => {
+                            let key = attributes(e)?.remove("key").unwrap_or_default();
+                            data = Some((key, String::new()));
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Text(e) => {
+                    if let Some((_, value)) = &mut data {
+                        value.push_str(&e.unescape()?);
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some((_, value)) = &mut data {
+                        value.push_str(&String::from_utf8_lossy(e));
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"data" => {
+                        if let Some((key, value)) = data.take() {
+                            match &mut current {
+                                Some((element, _)) => element.data.insert(key, value),
+                                None => graph.data.insert(key, value),
+                            };
+                        }
+                    }
+                    b"node" | b"edge" => graph.push(current.take()),
+                    _ => {}
+                },
+                Event::Eof => return Ok(graph),
+                _ => {}
+            }
+            buf.clear();
+        }
+    }
+
+    fn push(&mut self, element: Option<(GraphElement, bool)>) {
+        match element {
+            Some((element, true)) => self.edges.push(element),
+            Some((element, false)) => self.nodes.push(element),
+            None => {}
+        }
+    }
+
+    /// The value of the data key called `name`, among the `data` of an element.
+    fn value<'a>(&self, name: &str, data: &'a HashMap<String, String>) -> Option<&'a str> {
+        data.iter()
+            .find(|(key, _)| self.keys.get(*key).unwrap_or(key) == name)
+            .map(|(_, value)| value.trim())
+    }
+
+    fn number<T: FromStr>(&self, name: &str, data: &HashMap<String, String>) -> Result<Option<T>> {
+        match self.value(name, data) {
+            Some(value) => value.parse().map(Some).map_err(|_| {
+                SolverError::NetworkImportError(format!("{value} is not a valid {name}"))
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+fn attributes(e: &BytesStart) -> Result<HashMap<String, String>> {
+    e.attributes()
+        .map(|attribute| {
+            let attribute = attribute?;
+            Ok((
+                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                attribute.unescape_value()?.into_owned(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_graphml_round_trip() {
+        // capacities and costs in quanta, with a fixed arc and a name that needs escaping
+        let network: Network = serde_json::from_value(json!({
+            "vertices": [
+                { "name": "a & b", "x": 0.0, "y": 1.5 },
+                { "name": "c", "x": 2.0, "y": 0.0, "is_station": false },
+                { "name": "d", "x": 4.0, "y": 0.0 },
+            ],
+            "capacities": [[0, 3, 0], [1, 0, 4], [0, 0, 0]],
+            "costs": [[0, 2, 0], [2, 0, 5], [0, 0, 0]],
+            "balances": [[[0, 0, 0], [0, 0, 0], [0, 0, 0]]],
+            "fixed_arcs": [[1, 2]],
+            "quantum": 0.5,
+            "cost_quantum": 2.0,
+        }))
+        .unwrap();
+        let filename = std::env::temp_dir().join("robmcf_graphml_round_trip.graphml");
+        let filename = filename.to_str().unwrap();
+        network.to_graphml(filename, false).unwrap();
+        let imported = Network::from_graphml(&Options::default(), filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+
+        let names = |network: &Network| {
+            network
+                .vertices
+                .iter()
+                .map(|v| (v.name.clone(), v.x, v.y, v.is_station))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&network), names(&imported));
+        assert_eq!(network.capacities, imported.capacities);
+        assert_eq!(network.costs, imported.costs);
+        assert_eq!(network.fixed_arcs, imported.fixed_arcs);
+        assert_eq!(network.quantum, imported.quantum);
+        assert_eq!(network.cost_quantum, imported.cost_quantum);
+    }
+}
@@ -0,0 +1,733 @@
+//! Import of networks from GTFS feeds. Stops become vertices, merged into their parent station if
+//! they have one, and every pair of consecutive stops of a trip becomes an arc. Each trip is one
+//! unit of supply from the first to the last stop it serves, so capacities are given in trips.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{Matrix, Options, Result, SolverError};
+
+use super::{projection::project, Network, Vertex};
+
+#[derive(Deserialize)]
+struct Stop {
+    stop_id: String,
+    stop_name: Option<String>,
+    stop_lat: Option<f64>,
+    stop_lon: Option<f64>,
+    parent_station: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Route {
+    route_id: String,
+    route_type: u16,
+}
+
+#[derive(Deserialize)]
+struct Trip {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+}
+
+#[derive(Deserialize)]
+struct StopTime {
+    trip_id: String,
+    arrival_time: Option<String>,
+    departure_time: Option<String>,
+    stop_id: String,
+    stop_sequence: u32,
+    /// 1 if passengers cannot board here, e.g. at timing points.
+    pickup_type: Option<u8>,
+    /// 1 if passengers cannot alight here.
+    drop_off_type: Option<u8>,
+}
+
+impl StopTime {
+    fn has_passengers(&self) -> bool {
+        self.pickup_type != Some(1) || self.drop_off_type != Some(1)
+    }
+}
+
+#[derive(Deserialize)]
+struct Calendar {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Deserialize)]
+struct CalendarDate {
+    service_id: String,
+    date: String,
+    /// 1 if the service is added on the date, 2 if it is removed.
+    exception_type: u8,
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Days since 1970-01-01 of a GTFS date of "YYYYMMDD".
+fn parse_date(date: &str) -> Result<i64> {
+    let invalid = || SolverError::NetworkImportError(format!("{date} is not a valid date"));
+    let date = date.trim();
+    if date.len() != 8 || !date.is_ascii() {
+        return Err(invalid());
+    }
+    let year = date[0..4].parse::<i64>().map_err(|_| invalid())?;
+    let month = date[4..6].parse::<i64>().map_err(|_| invalid())?;
+    let day = date[6..8].parse::<i64>().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+    // counted from March on, so that leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Ok(era * 146097 + day_of_era - 719468)
+}
+
+/// Index into `WEEKDAYS` of a day since 1970-01-01, which was a Thursday.
+fn weekday(day: i64) -> usize {
+    (day + 3).rem_euclid(7) as usize
+}
+
+/// The days on which services run, from calendar.txt and the exceptions in calendar_dates.txt.
+struct ServiceCalendar {
+    calendar: HashMap<String, Calendar>,
+    exceptions: HashMap<(String, i64), bool>,
+}
+
+impl ServiceCalendar {
+    fn read(feed: &mut Feed) -> Result<Self> {
+        let calendar: Vec<Calendar> = feed.optional_records("calendar.txt")?;
+        let calendar_dates: Vec<CalendarDate> = feed.optional_records("calendar_dates.txt")?;
+        if calendar.is_empty() && calendar_dates.is_empty() {
+            return Err(SolverError::NetworkImportError(
+                "the feed has neither calendar.txt nor calendar_dates.txt".to_string(),
+            ));
+        }
+        let mut exceptions = HashMap::new();
+        for date in calendar_dates {
+            exceptions.insert(
+                (date.service_id, parse_date(&date.date)?),
+                date.exception_type == 1,
+            );
+        }
+        Ok(ServiceCalendar {
+            calendar: calendar
+                .into_iter()
+                .map(|calendar| (calendar.service_id.clone(), calendar))
+                .collect(),
+            exceptions,
+        })
+    }
+
+    fn runs_on(&self, service_id: &str, day: i64) -> Result<bool> {
+        if let Some(added) = self.exceptions.get(&(service_id.to_string(), day)) {
+            return Ok(*added);
+        }
+        let Some(calendar) = self.calendar.get(service_id) else {
+            return Ok(false);
+        };
+        let weekdays = [
+            calendar.monday,
+            calendar.tuesday,
+            calendar.wednesday,
+            calendar.thursday,
+            calendar.friday,
+            calendar.saturday,
+            calendar.sunday,
+        ];
+        Ok(weekdays[weekday(day)] == 1
+            && parse_date(&calendar.start_date)? <= day
+            && day <= parse_date(&calendar.end_date)?)
+    }
+
+    /// The first day on which any of the services runs.
+    fn first_day(&self, service_ids: &HashSet<&str>) -> Result<Option<i64>> {
+        let mut first = None;
+        for calendar in self.calendar.values() {
+            if service_ids.contains(calendar.service_id.as_str()) {
+                let start = parse_date(&calendar.start_date)?;
+                first = Some(first.map_or(start, |first: i64| first.min(start)));
+            }
+        }
+        for ((service_id, day), added) in self.exceptions.iter() {
+            if *added && service_ids.contains(service_id.as_str()) {
+                first = Some(first.map_or(*day, |first: i64| first.min(*day)));
+            }
+        }
+        Ok(first)
+    }
+}
+
+/// A GTFS feed, either unpacked into a directory or as a zip file. Tests hold their feeds in
+/// memory, as files by name.
+enum Feed {
+    Directory(PathBuf),
+    Zip(zip::ZipArchive<File>),
+    #[cfg(test)]
+    Memory(HashMap<&'static str, &'static str>),
+}
+
+impl Feed {
+    fn open(path: &str) -> Result<Self> {
+        match Path::new(path).is_dir() {
+            true => Ok(Feed::Directory(PathBuf::from(path))),
+            false => Ok(Feed::Zip(zip::ZipArchive::new(File::open(path)?)?)),
+        }
+    }
+
+    fn contains(&mut self, name: &str) -> bool {
+        match self {
+            Feed::Directory(directory) => directory.join(name).exists(),
+            Feed::Zip(archive) => archive.by_name(name).is_ok(),
+            #[cfg(test)]
+            Feed::Memory(files) => files.contains_key(name),
+        }
+    }
+
+    /// Records of a file which feeds may leave out, none if they do.
+    fn optional_records<T: DeserializeOwned>(&mut self, name: &str) -> Result<Vec<T>> {
+        match self.contains(name) {
+            true => self.records(name),
+            false => Ok(vec![]),
+        }
+    }
+
+    fn records<T: DeserializeOwned>(&mut self, name: &str) -> Result<Vec<T>> {
+        let missing = || SolverError::NetworkImportError(format!("the feed has no {name}"));
+        let mut bytes = Vec::new();
+        match self {
+            Feed::Directory(directory) => {
+                let path = directory.join(name);
+                if !path.exists() {
+                    return Err(missing());
+                }
+                bytes = std::fs::read(path)?;
+            }
+            Feed::Zip(archive) => match archive.by_name(name) {
+                Ok(mut file) => {
+                    file.read_to_end(&mut bytes)?;
+                }
+                Err(zip::result::ZipError::FileNotFound) => return Err(missing()),
+                Err(e) => return Err(e.into()),
+            },
+            #[cfg(test)]
+            Feed::Memory(files) => match files.get(name) {
+                Some(file) => bytes = file.as_bytes().to_vec(),
+                None => return Err(missing()),
+            },
+        }
+        // many feeds start their files with a byte order mark
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+        csv::Reader::from_reader(bytes)
+            .deserialize()
+            .collect::<std::result::Result<Vec<T>, _>>()
+            .map_err(|e| SolverError::NetworkImportError(format!("{name}: {e}")))
+    }
+}
+
+/// Positions of all stations, where those without coordinates are placed at the mean position of
+/// their neighbours with coordinates, or at the mean of all stations if they have none.
+fn place_unlocated(
+    positions: &[Option<(f32, f32)>],
+    capacities: &Matrix<usize>,
+) -> Vec<(f32, f32)> {
+    let unlocated = positions
+        .iter()
+        .filter(|position| position.is_none())
+        .count();
+    if unlocated > 0 {
+        log::warn!(
+            "{unlocated} stations have no coordinates, and are placed amid their neighbours."
+        );
+    }
+    let mean = |positions: Vec<(f32, f32)>| {
+        let len = positions.len().max(1) as f32;
+        let (x, y) = positions
+            .iter()
+            .fold((0.0, 0.0), |(x, y), (x_i, y_i)| (x + x_i, y + y_i));
+        (x / len, y / len)
+    };
+    let center = mean(positions.iter().flatten().copied().collect());
+    (0..positions.len())
+        .map(|v| {
+            positions[v].unwrap_or_else(|| {
+                let neighbours = (0..positions.len())
+                    .filter(|&u| *capacities.get(u, v) > 0 || *capacities.get(v, u) > 0)
+                    .filter_map(|u| positions[u])
+                    .collect::<Vec<_>>();
+                match neighbours.is_empty() {
+                    true => center,
+                    false => mean(neighbours),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Seconds since midnight of a GTFS time of "HH:MM:SS". Hours may exceed 24.
+fn parse_time(time: &Option<String>) -> Option<u32> {
+    let mut parts = time.as_deref()?.trim().split(':');
+    let mut seconds = 0;
+    for _ in 0..3 {
+        seconds = seconds * 60 + parts.next()?.parse::<u32>().ok()?;
+    }
+    Some(seconds)
+}
+
+impl Network {
+    /// Builds a network from the GTFS feed at `path`, either a directory or a zip file. Only
+    /// routes of the given `route_types` are imported, or all routes if none are given.
+    ///
+    /// Arcs cost their mean scheduled run time in minutes, and their capacity is the number of
+    /// trips using them. With `per_service`, every service day of the first week of the feed
+    /// becomes a scenario of its own, with the trips which run on it according to calendar.txt
+    /// and calendar_dates.txt, and arcs get the capacity of the busiest day. Otherwise, there is
+    /// a single scenario with all trips. Stops at which passengers can board or alight are
+    /// stations, and the others junctions. Stations without coordinates are placed amid their
+    /// neighbours.
+    pub fn from_gtfs(
+        options: &Options,
+        path: &str,
+        route_types: &[u16],
+        per_service: bool,
+    ) -> Result<Self> {
+        Network::from_feed(options, &mut Feed::open(path)?, route_types, per_service)
+    }
+
+    fn from_feed(
+        options: &Options,
+        feed: &mut Feed,
+        route_types: &[u16],
+        per_service: bool,
+    ) -> Result<Self> {
+        let stops: Vec<Stop> = feed.records("stops.txt")?;
+        let routes: Vec<Route> = feed.records("routes.txt")?;
+        let trips: Vec<Trip> = feed.records("trips.txt")?;
+        let stop_times: Vec<StopTime> = feed.records("stop_times.txt")?;
+
+        let route_ids = routes
+            .iter()
+            .filter(|route| route_types.is_empty() || route_types.contains(&route.route_type))
+            .map(|route| route.route_id.as_str())
+            .collect::<HashSet<_>>();
+        let trips = trips
+            .iter()
+            .filter(|trip| route_ids.contains(trip.route_id.as_str()))
+            .map(|trip| (trip.trip_id.as_str(), trip))
+            .collect::<HashMap<_, _>>();
+        let mut trip_stops: BTreeMap<&str, Vec<&StopTime>> = BTreeMap::new();
+        for stop_time in stop_times.iter() {
+            if trips.contains_key(stop_time.trip_id.as_str()) {
+                trip_stops
+                    .entry(stop_time.trip_id.as_str())
+                    .or_default()
+                    .push(stop_time);
+            }
+        }
+        for stop_times in trip_stops.values_mut() {
+            stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+        }
+        if trip_stops.is_empty() {
+            return Err(SolverError::NetworkImportError(
+                "the feed has no trips on routes of the given types".to_string(),
+            ));
+        }
+
+        // stops are merged into their parent station
+        let stops = stops
+            .iter()
+            .map(|stop| (stop.stop_id.as_str(), stop))
+            .collect::<HashMap<_, _>>();
+        let station = |stop_id: &str| -> Result<&Stop> {
+            let stop = stops.get(stop_id).ok_or_else(|| {
+                SolverError::NetworkImportError(format!("stop {stop_id} is not in stops.txt"))
+            })?;
+            Ok(match stop.parent_station.as_deref() {
+                Some(parent) => stops.get(parent).unwrap_or(stop),
+                None => stop,
+            })
+        };
+        let mut used_stations = BTreeSet::new();
+        for stop_time in trip_stops.values().flatten() {
+            used_stations.insert(station(&stop_time.stop_id)?.stop_id.as_str());
+        }
+        let indices = used_stations
+            .iter()
+            .enumerate()
+            .map(|(v, stop_id)| (*stop_id, v))
+            .collect::<HashMap<_, _>>();
+        let len = used_stations.len();
+
+        // the scenarios each service runs in
+        let service_ids = trips
+            .values()
+            .map(|trip| trip.service_id.as_str())
+            .collect::<HashSet<_>>();
+        let mut service_scenarios: HashMap<&str, Vec<usize>> = HashMap::new();
+        let num_scenarios = match per_service {
+            true => {
+                let calendar = ServiceCalendar::read(feed)?;
+                let Some(first_day) = calendar.first_day(&service_ids)? else {
+                    return Err(SolverError::NetworkImportError(
+                        "the calendar of the feed has no days with trips".to_string(),
+                    ));
+                };
+                let mut days = Vec::new();
+                for day in first_day..first_day + 7 {
+                    let mut runs = false;
+                    for service_id in service_ids.iter() {
+                        if calendar.runs_on(service_id, day)? {
+                            service_scenarios
+                                .entry(*service_id)
+                                .or_default()
+                                .push(days.len());
+                            runs = true;
+                        }
+                    }
+                    if runs {
+                        days.push(WEEKDAYS[weekday(day)]);
+                    }
+                }
+                if days.is_empty() {
+                    return Err(SolverError::NetworkImportError(
+                        "no trips run in the first week of the feed".to_string(),
+                    ));
+                }
+                log::info!("Scenarios are the service days {}.", days.join(", "));
+                days.len()
+            }
+            false => {
+                for service_id in service_ids.iter() {
+                    service_scenarios.insert(*service_id, vec![0]);
+                }
+                1
+            }
+        };
+
+        let mut balances = vec![Matrix::filled_with(0, len, len); num_scenarios];
+        let mut arc_trips = vec![Matrix::filled_with(0, len, len); num_scenarios];
+        let mut run_times = BTreeMap::new();
+        let mut is_station = vec![false; len];
+        for (trip_id, stop_times) in trip_stops.iter() {
+            let scenarios = service_scenarios
+                .get(trips[trip_id].service_id.as_str())
+                .map_or(&[][..], Vec::as_slice);
+            let vertices = stop_times
+                .iter()
+                .map(|stop_time| Ok(indices[station(&stop_time.stop_id)?.stop_id.as_str()]))
+                .collect::<Result<Vec<_>>>()?;
+            for (stop_time, v) in stop_times.iter().zip(vertices.iter()) {
+                is_station[*v] |= stop_time.has_passengers();
+            }
+            for (i, arc) in vertices.windows(2).enumerate() {
+                let (s, t) = (arc[0], arc[1]);
+                if s == t {
+                    continue;
+                }
+                for &lambda in scenarios {
+                    let trips_on_arc = *arc_trips[lambda].get(s, t);
+                    arc_trips[lambda].set(s, t, trips_on_arc + 1);
+                }
+                let departure = parse_time(&stop_times[i].departure_time);
+                let arrival = parse_time(&stop_times[i + 1].arrival_time);
+                if let (Some(departure), Some(arrival)) = (departure, arrival) {
+                    let (total, count) = run_times.entry((s, t)).or_insert((0, 0));
+                    *total += arrival.saturating_sub(departure) as usize;
+                    *count += 1;
+                }
+            }
+            let (first, last) = (vertices[0], vertices[vertices.len() - 1]);
+            if first != last {
+                for &lambda in scenarios {
+                    let supply = *balances[lambda].get(first, last);
+                    balances[lambda].set(first, last, supply + 1);
+                }
+            }
+        }
+
+        let mut capacities = Matrix::filled_with(0, len, len);
+        let mut costs = Matrix::filled_with(0, len, len);
+        for (s, t) in capacities.indices().collect::<Vec<_>>() {
+            let capacity = arc_trips
+                .iter()
+                .map(|trips| *trips.get(s, t))
+                .max()
+                .unwrap_or(0);
+            if capacity == 0 {
+                continue;
+            }
+            capacities.set(s, t, capacity);
+            // arcs without scheduled times cost a single minute
+            let cost = match run_times.get(&(s, t)) {
+                Some((total, count)) => (*total as f64 / *count as f64 / 60.0).round() as usize,
+                None => 1,
+            };
+            costs.set(s, t, cost.max(1));
+        }
+
+        let stations = used_stations
+            .iter()
+            .map(|stop_id| stops[stop_id])
+            .collect::<Vec<_>>();
+        let coordinates = stations
+            .iter()
+            .map(|stop| stop.stop_lat.zip(stop.stop_lon))
+            .collect::<Vec<_>>();
+        let (located, projection) =
+            project(&coordinates.iter().flatten().copied().collect::<Vec<_>>());
+        let mut located = located.into_iter();
+        let positions = coordinates
+            .iter()
+            .map(|coordinates| coordinates.and_then(|_| located.next()))
+            .collect::<Vec<_>>();
+        let positions = place_unlocated(&positions, &capacities);
+        let mut name_counts = HashMap::new();
+        for stop in stations.iter() {
+            *name_counts.entry(stop.stop_name.as_deref()).or_insert(0) += 1;
+        }
+        let vertices = stations
+            .iter()
+            .zip(positions)
+            .enumerate()
+            .map(|(v, (stop, (x, y)))| Vertex {
+                // names have to be unique, but stops on either side of a street often share one
+                name: match stop.stop_name.as_deref() {
+                    Some(name) if name_counts[&Some(name)] == 1 => name.to_string(),
+                    Some(name) => format!("{name} ({})", stop.stop_id),
+                    None => stop.stop_id.clone(),
+                },
+                x,
+                y,
+                is_station: is_station[v],
+                capacity: None,
+            })
+            .collect();
+
+        Ok(Network {
+            vertices,
+            capacities,
+            costs,
+            cost_metrics: BTreeMap::new(),
+            balances,
+            fixed_arcs: vec![],
+            quantum: 1.0,
+            cost_quantum: 1.0,
+            capacity_overrides: vec![],
+            arc_groups: vec![],
+            penalty_arcs: vec![],
+            projection: Some(projection),
+            baseline: None,
+            solutions: None,
+            auxiliary_network: None,
+            options: options.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            Some(8 * 3600 + 5 * 60 + 30),
+            parse_time(&Some("08:05:30".into()))
+        );
+        assert_eq!(Some(25 * 3600), parse_time(&Some(" 25:00:00".into())));
+        assert_eq!(None, parse_time(&Some("08:05".into())));
+        assert_eq!(None, parse_time(&None));
+    }
+
+    #[test]
+    fn test_from_feed() {
+        // both platforms of "s" are merged into it, "c" has no coordinates, and the rail trip
+        // "t3" is left out
+        let mut feed = Feed::Memory(HashMap::from([
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,parent_station
+s,Central,50.0,8.0,
+s1,Central 1,50.0,8.0,s
+s2,Central 2,50.0,8.0,s
+b,Market,50.0,8.1,
+c,Depot,,,
+",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_type
+bus,3
+rail,2
+",
+            ),
+            (
+                "trips.txt",
+                "route_id,service_id,trip_id
+bus,weekday,t1
+bus,weekday,t2
+rail,weekday,t3
+",
+            ),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence
+t1,08:00:00,08:00:00,s1,1
+t1,08:04:00,08:05:00,b,2
+t1,08:15:00,08:15:00,c,3
+t2,09:00:00,09:00:00,s2,1
+t2,09:06:00,09:06:00,b,2
+t3,10:00:00,10:00:00,c,1
+t3,10:30:00,10:30:00,s,2
+",
+            ),
+        ]));
+        let network = Network::from_feed(&Options::default(), &mut feed, &[3], false).unwrap();
+
+        let names = network
+            .vertices
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Market", "Depot", "Central"], names);
+        let (b, c, s) = (0, 1, 2);
+        assert_eq!(2, *network.capacities.get(s, b));
+        assert_eq!(1, *network.capacities.get(b, c));
+        assert_eq!(3, network.capacities.sum());
+        assert_eq!(5, *network.costs.get(s, b));
+        assert_eq!(10, *network.costs.get(b, c));
+        assert_eq!(1, network.balances.len());
+        assert_eq!(1, *network.balances[0].get(s, c));
+        assert_eq!(1, *network.balances[0].get(s, b));
+        assert_eq!(2, network.balances[0].sum());
+
+        // "c" is placed at "b", its only neighbour with coordinates
+        assert_eq!(
+            (network.vertices[b].x, network.vertices[b].y),
+            (network.vertices[c].x, network.vertices[c].y)
+        );
+        assert!(network.vertices[b].x > network.vertices[s].x);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(0, parse_date("19700101").unwrap());
+        assert_eq!(19723, parse_date("20240101").unwrap());
+        assert_eq!(19782, parse_date("20240229").unwrap());
+        assert_eq!(0, weekday(parse_date("20240101").unwrap()));
+        assert_eq!(6, weekday(parse_date("20241006").unwrap()));
+        assert!(parse_date("2024-1-1").is_err());
+        assert!(parse_date("20241301").is_err());
+    }
+
+    #[test]
+    fn test_from_feed_per_service_day() {
+        // the week of 2024-01-01, a Monday, without weekday service on Tuesday and with an
+        // extra trip on Wednesday, and a timing point "m" at which the weekend trip only passes
+        let mut feed = Feed::Memory(HashMap::from([
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,parent_station
+a,A,50.0,8.0,
+b,B,50.0,8.1,
+m,M,50.0,8.05,
+",
+            ),
+            ("routes.txt", "route_id,route_type\nbus,3\n"),
+            (
+                "trips.txt",
+                "route_id,service_id,trip_id
+bus,weekday,t1
+bus,weekend,t2
+bus,special,t3
+",
+            ),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence,pickup_type,drop_off_type
+t1,08:00:00,08:00:00,a,1,0,1
+t1,08:10:00,08:10:00,b,2,1,0
+t2,09:00:00,09:00:00,a,1,0,1
+t2,09:05:00,09:05:00,m,2,1,1
+t2,09:10:00,09:10:00,b,3,1,0
+t3,10:00:00,10:00:00,b,1,,
+t3,10:10:00,10:10:00,a,2,,
+",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date
+weekday,1,1,1,1,1,0,0,20240101,20241231
+weekend,0,0,0,0,0,1,1,20240101,20241231
+",
+            ),
+            (
+                "calendar_dates.txt",
+                "service_id,date,exception_type
+weekday,20240102,2
+special,20240103,1
+",
+            ),
+        ]));
+        let network = Network::from_feed(&Options::default(), &mut feed, &[], true).unwrap();
+
+        let (a, b, m) = (0, 1, 2);
+        assert_eq!(
+            vec![("A", true), ("B", true), ("M", false)],
+            network
+                .vertices
+                .iter()
+                .map(|v| (v.name.as_str(), v.is_station))
+                .collect::<Vec<_>>()
+        );
+
+        // Monday, Wednesday to Friday, Saturday and Sunday
+        assert_eq!(6, network.balances.len());
+        assert_eq!(
+            vec![1, 1, 1, 1, 1, 1],
+            network
+                .balances
+                .iter()
+                .map(|balance| *balance.get(a, b))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 1, 0, 0, 0, 0],
+            network
+                .balances
+                .iter()
+                .map(|balance| *balance.get(b, a))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, *network.capacities.get(a, b));
+        assert_eq!(1, *network.capacities.get(a, m));
+        assert_eq!(1, *network.capacities.get(b, a));
+    }
+}
@@ -1,27 +1,87 @@
+mod arc_group;
+mod binary;
+mod commodity_flow;
+mod comparison;
+mod diagnosis;
+mod dimacs;
 mod display;
+mod dot;
 mod export;
+mod flow_path;
+mod geojson;
+mod graphml;
+mod gtfs;
+mod osm;
+mod projection;
+mod quantum;
 mod random;
+mod report;
 mod solution;
+mod sparse;
+mod svg;
 mod to_latex;
 mod vertex;
+mod violation;
 
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::{collections::BTreeMap, fs};
 
 pub(super) use crate::auxiliary::AuxiliaryNetwork;
-use crate::{options::RemainderSolveMethod, Matrix, Options};
+use crate::{
+    options::{NetworkFormat, ObjectiveFunction, RemainderSolveMethod},
+    Matrix, Options,
+};
 use crate::{Result, SolverError};
+pub use arc_group::ArcGroup;
+pub(crate) use commodity_flow::record_commodity_flow;
+pub use commodity_flow::CommodityFlow;
+pub use comparison::{ArcChange, Change, RouteChange, ScenarioComparison, SolutionComparison};
+pub use diagnosis::{Bottleneck, InfeasibilityDiagnosis};
+pub(crate) use flow_path::record_path;
+pub use flow_path::FlowPath;
+pub use projection::Projection;
 pub(super) use solution::{ScenarioSolution, Solution};
+pub use svg::LoadShading;
 pub use vertex::Vertex;
+pub use violation::SolutionViolation;
+
+/// Name under which `Network::costs` is available as a cost metric.
+pub(crate) const PRIMARY_METRIC: &str = "costs";
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct Network {
     pub vertices: Vec<Vertex>,
     pub capacities: Matrix<usize>,
     pub costs: Matrix<usize>,
+    /// Additional named cost metrics, e.g. track-kilometres or energy.
+    #[serde(default)]
+    pub cost_metrics: BTreeMap<String, Matrix<usize>>,
     pub balances: Vec<Matrix<usize>>,
     pub fixed_arcs: Vec<(usize, usize)>,
 
+    /// Size of one supply unit in the units of the network file, allowing for fractional
+    /// capacities and demand. All supply-like values are stored as whole multiples of it.
+    #[serde(default = "default_quantum", skip_serializing_if = "is_unit_quantum")]
+    pub quantum: f64,
+    /// Size of one cost unit in the units of the network file.
+    #[serde(default = "default_quantum", skip_serializing_if = "is_unit_quantum")]
+    pub cost_quantum: f64,
+
+    /// Per-scenario capacity changes as triples of "s, t, capacity". A capacity of 0 closes the arc.
+    /// Fixed arcs with a changed capacity are limited to it, instead of being unlimited.
+    #[serde(default)]
+    pub capacity_overrides: Vec<Vec<(usize, usize, usize)>>,
+    #[serde(default)]
+    pub arc_groups: Vec<ArcGroup>,
+    /// Arcs standing in for not meeting the demand of their origin and destination. Only that
+    /// commodity may use them, and their load is reported as unmet demand.
+    #[serde(default)]
+    pub penalty_arcs: Vec<(usize, usize)>,
+    /// Projection by which the vertex positions of networks imported from geographic data were
+    /// obtained, and can be converted back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<Projection>,
+
     pub baseline: Option<Vec<ScenarioSolution>>,
     pub solutions: Option<Vec<ScenarioSolution>>,
 
@@ -31,16 +91,79 @@ pub struct Network {
     pub options: Options,
 }
 
+fn default_quantum() -> f64 {
+    1.0
+}
+
+fn is_unit_quantum(quantum: &f64) -> bool {
+    *quantum == 1.0
+}
+
 impl Network {
+    /// Reads a network in dense or sparse JSON or, if the file starts with its magic bytes, the
+    /// binary format.
     pub fn from_file(options: &Options, filename: &str) -> Result<Self> {
-        let network_string = fs::read_to_string(filename)?;
-        let mut network: Network = serde_json::from_str(&network_string)?;
+        let bytes = fs::read(filename)?;
+        let mut network = match binary::is_binary(&bytes) {
+            true => binary::from_bytes(&bytes)?,
+            false => {
+                let mut network_value: serde_json::Value = serde_json::from_slice(&bytes)?;
+                if sparse::is_sparse(&network_value) {
+                    network_value = sparse::to_dense(network_value)?;
+                }
+                quantum::quantize(&mut network_value);
+                serde_json::from_value(network_value)?
+            }
+        };
         network.options = options.clone();
         Ok(network)
     }
 
+    /// The format in which the network file `filename` is stored.
+    pub fn format_of(filename: &str) -> Result<NetworkFormat> {
+        let bytes = fs::read(filename)?;
+        if binary::is_binary(&bytes) {
+            return Ok(NetworkFormat::Binary);
+        }
+        let network_value: serde_json::Value = serde_json::from_slice(&bytes)?;
+        match sparse::is_sparse(&network_value) {
+            true => Ok(NetworkFormat::Sparse),
+            false => Ok(NetworkFormat::Dense),
+        }
+    }
+
+    /// Writes the network in dense JSON or, for files ending in `.rmcf`, in the binary format.
     pub fn serialize(&self, filename: &str) -> Result<()> {
-        let json_str = serde_json::to_string(self)?;
+        match binary::is_binary_filename(filename) {
+            true => self.serialize_as(filename, NetworkFormat::Binary),
+            false => self.serialize_as(filename, NetworkFormat::Dense),
+        }
+    }
+
+    pub fn serialize_as(&self, filename: &str, format: NetworkFormat) -> Result<()> {
+        let json_str = match format {
+            NetworkFormat::Binary => {
+                log::debug!("Writing binary network to {filename}");
+                std::fs::write(filename, binary::to_bytes(self)?)?;
+                return Ok(());
+            }
+            NetworkFormat::Dense
+                if is_unit_quantum(&self.quantum) && is_unit_quantum(&self.cost_quantum) =>
+            {
+                serde_json::to_string(self)?
+            }
+            NetworkFormat::Dense => {
+                let mut network_value = serde_json::to_value(self)?;
+                quantum::dequantize(&mut network_value);
+                serde_json::to_string(&network_value)?
+            }
+            NetworkFormat::Sparse => {
+                let mut network_value = serde_json::to_value(self)?;
+                quantum::dequantize(&mut network_value);
+                // meant to be edited by hand
+                serde_json::to_string_pretty(&sparse::to_sparse(network_value)?)?
+            }
+        };
         log::debug!("Writing\n{json_str}\nto {filename}");
         std::fs::write(filename, json_str)?;
         Ok(())
@@ -71,11 +194,198 @@ impl Network {
         Ok(())
     }
 
+    /// All cost metrics by name, starting with the primary `costs`.
+    pub fn metrics(&self) -> Vec<(&str, &Matrix<usize>)> {
+        std::iter::once((PRIMARY_METRIC, &self.costs))
+            .chain(
+                self.cost_metrics
+                    .iter()
+                    .map(|(name, metric)| (name.as_str(), metric)),
+            )
+            .collect()
+    }
+
+    pub fn metric(&self, name: &str) -> Option<&Matrix<usize>> {
+        match name {
+            PRIMARY_METRIC => Some(&self.costs),
+            _ => self.cost_metrics.get(name),
+        }
+    }
+
+    /// The cost metric selected for routing. Falls back to `costs` for unknown metrics, which
+    /// `validate_network` rejects.
+    pub fn routing_costs(&self) -> &Matrix<usize> {
+        self.metric(&self.options.routing_metric)
+            .unwrap_or(&self.costs)
+    }
+
+    /// The cost metrics to be minimized by the ILPs, along with their weights.
+    pub(crate) fn objective_metrics(&self) -> Vec<(&Matrix<usize>, f64)> {
+        if matches!(self.options.objective_fn, ObjectiveFunction::Single) {
+            return vec![(self.routing_costs(), 1.0)];
+        }
+        let metrics = match self.options.objective_metrics.is_empty() {
+            true => self.metrics(),
+            false => self
+                .options
+                .objective_metrics
+                .iter()
+                .filter_map(|name| self.metric(name).map(|metric| (name.as_str(), metric)))
+                .collect(),
+        };
+        metrics
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, metric))| {
+                (
+                    metric,
+                    *self.options.objective_weights.get(i).unwrap_or(&1.0),
+                )
+            })
+            .collect()
+    }
+
+    /// Arcs which have a capacity or are fixed.
+    pub(crate) fn arcs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.capacities
+            .indices()
+            .filter(|&(s, t)| *self.capacities.get(s, t) > 0 || self.fixed_arcs.contains(&(s, t)))
+    }
+
+    /// Solutions ordered by scenario.
+    pub(crate) fn sorted_solutions(&self) -> Result<Vec<&ScenarioSolution>> {
+        let Some(solutions) = &self.solutions else {
+            return Err(SolverError::SkippedSolveError);
+        };
+        let mut solutions = solutions.iter().collect::<Vec<_>>();
+        solutions.sort_by_key(|solution| solution.id);
+        Ok(solutions)
+    }
+
+    pub fn scenario_capacities(&self, lambda: usize) -> Matrix<usize> {
+        let mut capacities = self.capacities.clone();
+        for &(s, t, capacity) in self.capacity_overrides.get(lambda).into_iter().flatten() {
+            capacities.set(s, t, capacity);
+        }
+        capacities
+    }
+
+    pub(crate) fn has_capacity_overrides(&self, lambda: usize) -> bool {
+        self.capacity_overrides
+            .get(lambda)
+            .is_some_and(|overrides| !overrides.is_empty())
+    }
+
+    pub(crate) fn is_overridden(&self, lambda: usize, s: usize, t: usize) -> bool {
+        self.capacity_overrides
+            .get(lambda)
+            .into_iter()
+            .flatten()
+            .any(|&(a_0, a_1, _)| (a_0, a_1) == (s, t))
+    }
+
+    /// Fixed arcs have unlimited capacity, unless the scenario overrides it.
+    pub(crate) fn is_lifted(&self, lambda: usize, s: usize, t: usize) -> bool {
+        self.fixed_arcs.contains(&(s, t)) && !self.is_overridden(lambda, s, t)
+    }
+
+    /// Arc groups as they apply to scenario `lambda`: lifted fixed arcs are not part of any group,
+    /// and the group capacities are reduced by the given existing arc loads.
+    pub(crate) fn scenario_arc_groups(
+        &self,
+        lambda: usize,
+        lift_capacity_constraints: bool,
+        arc_loads: Option<&Matrix<usize>>,
+    ) -> Vec<ArcGroup> {
+        self.arc_groups
+            .iter()
+            .map(|group| {
+                let arcs = group
+                    .arcs
+                    .iter()
+                    .filter(|(s, t)| !(lift_capacity_constraints && self.is_lifted(lambda, *s, *t)))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let used = arc_loads.map_or(0, |arc_loads| {
+                    arcs.iter().map(|(s, t)| *arc_loads.get(*s, *t)).sum()
+                });
+                ArcGroup {
+                    arcs,
+                    capacity: group.capacity.saturating_sub(used),
+                }
+            })
+            .collect()
+    }
+
+    /// Capacities of scenario `lambda` as the greedy sees them: lifted fixed arcs are unlimited,
+    /// and arcs in groups are limited by the capacity of their group.
+    pub(crate) fn greedy_capacities(&self, lambda: usize) -> Matrix<usize> {
+        let mut scenario_capacities = self.scenario_capacities(lambda);
+        for (a_0, a_1) in &self.fixed_arcs {
+            if self.is_lifted(lambda, *a_0, *a_1) {
+                scenario_capacities.set(*a_0, *a_1, usize::MAX);
+            }
+        }
+        for group in self.scenario_arc_groups(lambda, true, None).iter() {
+            for (a_0, a_1) in group.arcs.iter() {
+                if *scenario_capacities.get(*a_0, *a_1) > group.capacity {
+                    scenario_capacities.set(*a_0, *a_1, group.capacity);
+                }
+            }
+        }
+        scenario_capacities
+    }
+
+    /// Vertex capacities reduced by the given existing arc loads. Vertices without a capacity are
+    /// unlimited.
+    pub(crate) fn residual_vertex_capacities(
+        &self,
+        arc_loads: Option<&Matrix<usize>>,
+    ) -> Vec<Option<usize>> {
+        self.vertices
+            .iter()
+            .enumerate()
+            .map(|(v, vertex)| {
+                vertex.capacity.map(|capacity| {
+                    let used = arc_loads.map_or(0, |arc_loads| {
+                        (0..self.vertices.len()).map(|u| *arc_loads.get(u, v)).sum()
+                    });
+                    capacity.saturating_sub(used)
+                })
+            })
+            .collect()
+    }
+
+    /// Bidirectional arc groups without fixed arcs, which drawings show as a single track.
+    pub(crate) fn tracks(&self) -> Vec<&ArcGroup> {
+        self.arc_groups
+            .iter()
+            .filter(|group| {
+                group.is_bidirectional()
+                    && !group.arcs.iter().any(|arc| self.fixed_arcs.contains(arc))
+            })
+            .collect()
+    }
+
+    /// Consistent flows of the solution as loads on the fixed arcs, with all other arcs empty.
+    pub(crate) fn consistent_flow_loads(&self) -> Result<Matrix<usize>> {
+        let Some(solutions) = self.solutions.as_deref().filter(|s| !s.is_empty()) else {
+            return Err(SolverError::SkippedSolveError);
+        };
+        let consistent_flows = solutions.consistent_flows(&self.fixed_arcs);
+        let len = self.vertices.len();
+        let mut loads = Matrix::filled_with(0, len, len);
+        for &(s, t) in self.fixed_arcs.iter() {
+            loads.set(s, t, *consistent_flows.get(s, t) as usize);
+        }
+        Ok(loads)
+    }
+
     pub fn validate_network(&self) -> Result<()> {
         let len = self.vertices.len();
 
         let matrices = [&self.capacities, &self.costs];
-        for matrix in matrices {
+        for matrix in matrices.into_iter().chain(self.cost_metrics.values()) {
             if matrix.num_rows() != len || matrix.num_columns() != len {
                 return Err(SolverError::NetworkShapeError(
                     "capacities, and costs have differing dimensions or are not quadratic"
@@ -84,6 +394,35 @@ impl Network {
             }
         }
 
+        if self.cost_metrics.contains_key(PRIMARY_METRIC) {
+            return Err(SolverError::NetworkShapeError(format!(
+                "the cost metric \"{PRIMARY_METRIC}\" is reserved for the primary costs"
+            )));
+        }
+        for name in std::iter::once(&self.options.routing_metric)
+            .chain(self.options.objective_metrics.iter())
+        {
+            if self.metric(name).is_none() {
+                return Err(SolverError::NetworkShapeError(format!(
+                    "unknown cost metric \"{name}\", available are: {}",
+                    self.metrics()
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+        if !self.options.objective_weights.is_empty()
+            && self.options.objective_weights.len() != self.objective_metrics().len()
+        {
+            return Err(SolverError::NetworkShapeError(format!(
+                "{} objective weights were given for {} objective metrics",
+                self.options.objective_weights.len(),
+                self.objective_metrics().len()
+            )));
+        }
+
         for (i, row) in self.capacities.as_rows().iter().enumerate() {
             if row.iter().sum::<usize>() == 0 {
                 return Err(SolverError::NetworkShapeError(format!(
@@ -101,7 +440,38 @@ impl Network {
             }
         }
 
-        let total_capacity = self.capacities.sum();
+        if self.capacity_overrides.len() > self.balances.len() {
+            return Err(SolverError::NetworkShapeError(format!(
+                "found capacity overrides for {} scenarios, but only {} scenarios exist",
+                self.capacity_overrides.len(),
+                self.balances.len()
+            )));
+        }
+        for (i, overrides) in self.capacity_overrides.iter().enumerate() {
+            if let Some((s, t, _)) = overrides.iter().find(|(s, t, _)| *s >= len || *t >= len) {
+                return Err(SolverError::NetworkShapeError(format!(
+                    "scenario {i} overrides the capacity of arc ({s},{t}), but only {len} vertices exist"
+                )));
+            }
+        }
+
+        for group in self.arc_groups.iter() {
+            if let Some((s, t)) = group.arcs.iter().find(|(s, t)| *s >= len || *t >= len) {
+                return Err(SolverError::NetworkShapeError(format!(
+                    "an arc group contains arc ({s},{t}), but only {len} vertices exist"
+                )));
+            }
+        }
+        if let Some((s, t)) = self
+            .penalty_arcs
+            .iter()
+            .find(|(s, t)| *s >= len || *t >= len)
+        {
+            return Err(SolverError::NetworkShapeError(format!(
+                "penalty arc ({s},{t}) is out of bounds, only {len} vertices exist"
+            )));
+        }
+
         for (i, matrix) in self.balances.iter().enumerate() {
             if matrix.num_rows() != len || matrix.num_columns() != len {
                 return Err(SolverError::NetworkShapeError(
@@ -131,13 +501,35 @@ impl Network {
                 }
             }
 
-            if total_capacity < matrix.sum() {
+            for (v, vertex) in self.vertices.iter().enumerate() {
+                let demand = as_columns[v].iter().sum::<usize>();
+                if vertex.capacity.is_some_and(|capacity| capacity < demand) {
+                    return Err(SolverError::NetworkShapeError(format!(
+                        "scenario {i} has demand {demand} at vertex {vertex}, but its capacity is {}",
+                        vertex.capacity.unwrap_or_default()
+                    )));
+                }
+            }
+
+            if self.scenario_capacities(i).sum() < matrix.sum() {
                 return Err(SolverError::NetworkShapeError(format!(
                     "scenario {i} has higher supply than the network has capacities"
                 )));
             }
         }
 
+        // Max-flow pre-check, to fail before the expensive preprocessing
+        let mut diagnoses = (0..self.balances.len())
+            .map(|i| self.diagnose_infeasibility(i))
+            .filter(|diagnosis| !diagnosis.bottlenecks.is_empty())
+            .collect::<Vec<_>>();
+        if !diagnoses.is_empty() {
+            for diagnosis in diagnoses.iter().skip(1) {
+                log::warn!("{diagnosis}");
+            }
+            return Err(SolverError::InfeasibleScenarioError(diagnoses.remove(0)));
+        }
+
         log::info!("Network is valid.");
         Ok(())
     }
@@ -147,7 +539,10 @@ impl Network {
         match self.auxiliary_network {
             Some(_) => {}
             None => {
-                let auxiliary_network = AuxiliaryNetwork::from_network(&*self)?;
+                let auxiliary_network = match &self.options.preprocessing_cache {
+                    Some(directory) => AuxiliaryNetwork::from_cache(&*self, directory)?,
+                    None => AuxiliaryNetwork::from_network(&*self)?,
+                };
                 self.auxiliary_network = Some(auxiliary_network);
             }
         }
@@ -164,10 +559,10 @@ impl Network {
         match crate::ilp::gurobi_partial(self, true) {
             Err(e) => {
                 self.capacities = capacities_memory;
-                Err(e)
+                Err(self.explain_infeasibility(e))
             }
             Ok(solutions) => {
-                self.baseline = Some(solutions);
+                self.baseline = Some(self.separate_unmet_demand(solutions));
                 self.capacities = capacities_memory;
                 log::info!("Found a lower bound on network cost.");
                 Ok(())
@@ -179,11 +574,11 @@ impl Network {
         log::info!("Attempting to calculate the original flow...");
         match crate::ilp::gurobi_partial(self, false) {
             Ok(solutions) => {
-                self.baseline = Some(solutions);
+                self.baseline = Some(self.separate_unmet_demand(solutions));
                 log::info!("Calculated the original flow.");
                 Ok(())
             }
-            Err(e) => Err(e),
+            Err(e) => Err(self.explain_infeasibility(e)),
         }
     }
 
@@ -196,9 +591,10 @@ impl Network {
 
         let mut candidates = Matrix::filled_with(0, self.vertices.len(), self.vertices.len());
         for scenario in baseline {
+            let capacities = self.scenario_capacities(scenario.id);
             for (i, j) in scenario.arc_loads.indices() {
                 let arc_load = *scenario.arc_loads.get(i, j);
-                let capacity = *self.capacities.get(i, j);
+                let capacity = *capacities.get(i, j);
                 if capacity > 0 && arc_load == capacity {
                     candidates.increment(i, j);
                 }
@@ -233,22 +629,102 @@ impl Network {
         Ok(())
     }
 
-    pub fn add_penalty_arcs(&mut self) -> Result<()> {
+    /// Adds a penalty arc for every OD pair with demand but without a direct arc. Routing a unit
+    /// of supply over it costs `penalty_cost`, unless `od_penalty_costs` holds a triple of
+    /// "s, t, cost" for the pair. The default penalty is higher than the cost of any path through
+    /// the network.
+    pub fn add_penalty_arcs(
+        &mut self,
+        penalty_cost: Option<usize>,
+        od_penalty_costs: &[(usize, usize, usize)],
+    ) -> Result<()> {
+        let len = self.vertices.len();
+        if self
+            .balances
+            .iter()
+            .any(|b| b.num_rows() != len || b.num_columns() != len)
+        {
+            return Err(SolverError::NetworkShapeError(
+                "some balances have differing dimensions or are not quadratic".to_owned(),
+            ));
+        }
+        if let Some((s, t, _)) = od_penalty_costs
+            .iter()
+            .find(|(s, t, _)| *s >= len || *t >= len)
+        {
+            return Err(SolverError::NetworkShapeError(format!(
+                "Attempted to set a penalty cost for OD pair ({s},{t}), but only {len} vertices exist."
+            )));
+        }
+        let penalty_cost = penalty_cost.unwrap_or_else(|| {
+            len.max(1)
+                * self
+                    .metrics()
+                    .iter()
+                    .map(|(_, metric)| metric.max())
+                    .max()
+                    .unwrap_or(1)
+                    .max(1)
+        });
+
         let indices = self
             .capacities
             .indices()
-            .filter(|&(s, t)| *self.capacities.get(s, t) == 0)
+            .filter(|&(s, t)| s != t && *self.capacities.get(s, t) == 0)
             .collect::<Vec<_>>();
         for (s, t) in indices {
-            if self.balances.iter().any(|b| *b.get(s, t) > 0) {
-                self.capacities.set(s, t, usize::MAX);
-                self.costs.set(s, t, usize::MAX / 2);
+            let demand = self
+                .balances
+                .iter()
+                .map(|b| *b.get(s, t))
+                .max()
+                .unwrap_or(0);
+            if demand == 0 {
+                continue;
             }
+            let cost = od_penalty_costs
+                .iter()
+                .find(|(a_0, a_1, _)| (*a_0, *a_1) == (s, t))
+                .map_or(penalty_cost, |(_, _, cost)| *cost);
+
+            self.capacities.set(s, t, demand);
+            self.costs.set(s, t, cost);
+            for metric in self.cost_metrics.values_mut() {
+                metric.set(s, t, cost);
+            }
+            self.penalty_arcs.push((s, t));
         }
-        log::info!("Added penalty arcs.");
+        log::info!("Added {} penalty arcs.", self.penalty_arcs.len());
         Ok(())
     }
 
+    /// Restricts penalty arcs to the intermediate arc set of their own OD pair.
+    pub(crate) fn restrict_penalty_arcs(&self, arc_sets: &mut Matrix<Matrix<bool>>) {
+        for (s, t) in arc_sets.indices().collect::<Vec<_>>() {
+            for &(a_0, a_1) in self.penalty_arcs.iter() {
+                arc_sets.get_mut(s, t).set(a_0, a_1, (s, t) == (a_0, a_1));
+            }
+        }
+    }
+
+    /// Moves the load on penalty arcs out of the arc loads and into the unmet demand.
+    fn separate_unmet_demand(&self, mut solutions: Vec<ScenarioSolution>) -> Vec<ScenarioSolution> {
+        for solution in solutions.iter_mut() {
+            if solution.unmet_demand.num_rows() == 0 {
+                solution.unmet_demand =
+                    Matrix::filled_with(0, self.vertices.len(), self.vertices.len());
+            }
+            for &(s, t) in self.penalty_arcs.iter() {
+                let load = *solution.arc_loads.get(s, t);
+                solution.arc_loads.set(s, t, 0);
+                solution
+                    .unmet_demand
+                    .set(s, t, *solution.unmet_demand.get(s, t) + load);
+            }
+        }
+        solutions
+    }
+
     pub fn solve(&mut self) -> Result<()> {
         log::info!("Attempting to find a feasible robust flow...");
         let auxiliary_network = match &mut self.auxiliary_network {
@@ -259,22 +735,44 @@ impl Network {
 
         match crate::algorithms::greedy(auxiliary_network, &self.options) {
             Ok(solutions) => {
-                self.solutions = Some(solutions);
+                self.solutions = Some(self.separate_unmet_demand(solutions));
                 log::info!("Found a solution.");
                 Ok(())
             }
-            Err(e) => Err(e),
+            Err(e) => Err(self.explain_infeasibility(e)),
         }
     }
 
     pub fn solve_full_ilp(&mut self) -> Result<()> {
         log::info!("Attempting to solve the network as an ILP...");
-        match crate::ilp::gurobi_full(self) {
+        self.full_ilp(None)
+    }
+
+    /// Solves the network as an ILP, starting from the existing (e.g. greedy) solution.
+    pub fn warm_start_full_ilp(&mut self) -> Result<()> {
+        log::info!(
+            "Attempting to solve the network as an ILP, starting from the existing solution..."
+        );
+        match self.solutions.clone() {
+            Some(solutions) => self.full_ilp(Some(&solutions)),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    fn full_ilp(&mut self, warm_start: Option<&[ScenarioSolution]>) -> Result<()> {
+        match crate::ilp::gurobi_full(self, warm_start) {
             Ok(solutions) => {
-                self.solutions = Some(solutions);
+                self.solutions = Some(self.separate_unmet_demand(solutions));
                 log::info!("Found a solution.");
                 Ok(())
             }
+            // the full ILP solves all scenarios at once, so any of them may be to blame
+            Err(SolverError::GurobiSolutionError(lambda)) => Err((0..self.balances.len())
+                .map(|lambda| self.diagnose_infeasibility(lambda))
+                .find(|diagnosis| !diagnosis.bottlenecks.is_empty())
+                .map_or(SolverError::GurobiSolutionError(lambda), |diagnosis| {
+                    SolverError::InfeasibleScenarioError(diagnosis)
+                })),
             Err(e) => Err(e),
         }
     }
@@ -286,13 +784,79 @@ impl Network {
             RemainderSolveMethod::Greedy => log::debug!("No need to solve remaining network."),
             RemainderSolveMethod::Gurobi => {
                 log::info!("Passing the remaining unsolved network to Gurobi...");
-                let solutions = crate::ilp::gurobi_partial(self, false)?;
-                self.solutions = Some(solutions);
+                let solutions = crate::ilp::gurobi_partial(self, false)
+                    .map_err(|e| self.explain_infeasibility(e))?;
+                self.solutions = Some(self.separate_unmet_demand(solutions));
             }
         }
         Ok(())
     }
 
+    pub fn cost(&self) -> Result<usize> {
+        match &self.solutions {
+            Some(solutions) => Ok(solutions.cost(self.routing_costs(), &self.options.cost_fn)),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    pub fn baseline_cost(&self) -> Result<usize> {
+        match &self.baseline {
+            Some(baseline) => Ok(baseline.cost(self.routing_costs(), &self.options.cost_fn)),
+            None => Err(SolverError::SkippedBaselineError),
+        }
+    }
+
+    /// Cost of the solution in every cost metric.
+    pub fn metric_costs(&self) -> Result<Vec<(String, usize)>> {
+        match &self.solutions {
+            Some(solutions) => Ok(self
+                .metrics()
+                .into_iter()
+                .map(|(name, metric)| {
+                    (
+                        name.to_string(),
+                        solutions.cost(metric, &self.options.cost_fn),
+                    )
+                })
+                .collect()),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    pub fn robustness_coefficient(&self) -> Result<f64> {
+        match &self.solutions {
+            Some(solutions) => Ok(solutions.robustness_coefficient(&self.fixed_arcs)),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    /// Paths of all scenarios which route supply over arc `(s, t)`, along with their scenario.
+    pub fn paths_through_arc(&self, s: usize, t: usize) -> Result<Vec<(usize, &FlowPath)>> {
+        match &self.solutions {
+            Some(solutions) => Ok(solutions
+                .iter()
+                .flat_map(|solution| solution.paths_through(s, t).map(|path| (solution.id, path)))
+                .collect()),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
+    /// Paths of all scenarios taken by the supply from `origin` to `destination`, along with
+    /// their scenario.
+    pub fn routes(&self, origin: usize, destination: usize) -> Result<Vec<(usize, &FlowPath)>> {
+        match &self.solutions {
+            Some(solutions) => Ok(solutions
+                .iter()
+                .flat_map(|solution| {
+                    solution
+                        .routes(origin, destination)
+                        .map(|path| (solution.id, path))
+                })
+                .collect()),
+            None => Err(SolverError::SkippedSolveError),
+        }
+    }
+
     pub fn validate_solution(&self) -> Result<()> {
         log::info!("Attempting to assess validity of found solution...");
         let solutions = match &self.solutions {
@@ -309,41 +873,524 @@ impl Network {
             )));
         }
 
+        let name = |v: usize| self.vertices[v].to_string();
         for solution in solutions {
-            for (s, t) in self
-                .capacities
+            let capacities = self.scenario_capacities(solution.id);
+            for (s, t) in capacities
                 .indices()
-                .filter(|&(s, t)| s != t && !self.fixed_arcs.contains(&(s, t)))
+                .filter(|&(s, t)| s != t && !self.is_lifted(solution.id, s, t))
             {
-                if *self.capacities.get(s, t) < *solution.arc_loads.get(s, t) {
-                    return Err(SolverError::NetworkShapeError(format!(
-                        "Scenario {} puts load {} on arc ({}->{}), but its capacity is {}.",
-                        solution.id,
-                        solution.arc_loads.get(s, t),
-                        self.vertices[s],
-                        self.vertices[t],
-                        self.capacities.get(s, t)
-                    )));
+                if *capacities.get(s, t) < *solution.arc_loads.get(s, t) {
+                    return Err(SolverError::InvalidSolutionError(
+                        SolutionViolation::ArcCapacity {
+                            scenario: solution.id,
+                            arc: (name(s), name(t)),
+                            load: *solution.arc_loads.get(s, t),
+                            capacity: *capacities.get(s, t),
+                        },
+                    ));
+                }
+            }
+        }
+
+        for solution in solutions {
+            for group in self.scenario_arc_groups(solution.id, true, None) {
+                let load = group.load(&solution.arc_loads);
+                if load > group.capacity {
+                    return Err(SolverError::InvalidSolutionError(
+                        SolutionViolation::SharedCapacity {
+                            scenario: solution.id,
+                            arcs: group
+                                .arcs
+                                .iter()
+                                .map(|&(s, t)| (name(s), name(t)))
+                                .collect(),
+                            load,
+                            capacity: group.capacity,
+                        },
+                    ));
+                }
+            }
+        }
+
+        for solution in solutions {
+            for (v, capacity) in self.residual_vertex_capacities(None).iter().enumerate() {
+                // Penalty arcs pass through their endpoints like any other arc in the greedy and
+                // the ILPs, so their loads, which are kept as unmet demand, count as well.
+                let load = (0..self.vertices.len())
+                    .map(|u| {
+                        let mut load = *solution.arc_loads.get(u, v);
+                        if self.penalty_arcs.contains(&(u, v)) {
+                            load += *solution.unmet_demand.get(u, v);
+                        }
+                        load
+                    })
+                    .sum::<usize>();
+                if let Some(capacity) = capacity.filter(|capacity| *capacity < load) {
+                    return Err(SolverError::InvalidSolutionError(
+                        SolutionViolation::VertexCapacity {
+                            scenario: solution.id,
+                            vertex: name(v),
+                            load,
+                            capacity,
+                        },
+                    ));
                 }
             }
         }
 
         for solution in solutions {
             let total_supply = self.balances[solution.id].sum();
-            let excessions = solution
+            if let Some((s, t)) = solution
                 .arc_loads
                 .indices()
-                .filter(|(s, t)| *solution.arc_loads.get(*s, *t) > total_supply)
-                .collect::<Vec<_>>();
-            if !excessions.is_empty() {
-                return Err(SolverError::NetworkShapeError(format!(
-                    "Scenario {} has {} supply, but the following arcs exceed this: {:?}",
-                    solution.id, total_supply, excessions
-                )));
+                .find(|(s, t)| *solution.arc_loads.get(*s, *t) > total_supply)
+            {
+                return Err(SolverError::InvalidSolutionError(
+                    SolutionViolation::ExcessiveArcLoad {
+                        scenario: solution.id,
+                        arc: (name(s), name(t)),
+                        load: *solution.arc_loads.get(s, t),
+                        supply: total_supply,
+                    },
+                ));
             }
         }
 
+        for solution in solutions {
+            self.validate_commodity_flows(solution)?;
+        }
+
+        // The greedy does not read the slack budgets, only solutions of the full ILP adhere to them
+        match self.options.remainder_solve_method {
+            RemainderSolveMethod::Ilp => self.validate_slack_budgets(solutions)?,
+            _ => log::debug!("Skipping the slack budgets, which only the full ILP adheres to."),
+        }
+
         log::info!("Solution is valid.");
         Ok(())
     }
+
+    fn validate_slack_budgets(&self, solutions: &[ScenarioSolution]) -> Result<()> {
+        let slack_budgets = self.options.slack_fn.apply(&self.balances);
+        for (solution, slack) in solutions.iter().zip(self.recompute_slack(solutions)) {
+            let budget = slack_budgets[solution.id];
+            if slack > budget {
+                return Err(SolverError::InvalidSolutionError(
+                    SolutionViolation::SlackBudget {
+                        scenario: solution.id,
+                        slack,
+                        budget,
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the flow of each OD pair is conserved, delivers exactly the supply that has
+    /// been routed, and that all flows together make up the arc loads. Solutions without
+    /// recorded commodity flows, e.g. from older network files, are skipped.
+    fn validate_commodity_flows(&self, solution: &ScenarioSolution) -> Result<()> {
+        if solution.commodity_flows.is_empty() {
+            log::debug!(
+                "Scenario {} has no commodity flows, skipping their validation.",
+                solution.id
+            );
+            return Ok(());
+        }
+        let name = |v: usize| self.vertices[v].to_string();
+        let balance = &self.balances[solution.id];
+
+        for (s, t) in balance.indices().filter(|&(s, t)| s != t) {
+            let remaining = *solution.supply_remaining.get(s, t);
+            let Some(expected) = balance.get(s, t).checked_sub(remaining) else {
+                return Err(SolverError::InvalidSolutionError(
+                    SolutionViolation::RemainingSupply {
+                        scenario: solution.id,
+                        origin: name(s),
+                        destination: name(t),
+                        remaining,
+                        supply: *balance.get(s, t),
+                    },
+                ));
+            };
+            let flow = solution
+                .commodity_flows
+                .iter()
+                .find(|flow| (flow.origin, flow.destination) == (s, t));
+            let delivered = flow.map_or(0, |flow| flow.net_outflow(s).max(0) as usize);
+            if delivered != expected {
+                return Err(SolverError::InvalidSolutionError(
+                    SolutionViolation::DeliveredSupply {
+                        scenario: solution.id,
+                        origin: name(s),
+                        destination: name(t),
+                        delivered,
+                        expected,
+                    },
+                ));
+            }
+        }
+
+        for flow in solution.commodity_flows.iter() {
+            for v in (0..self.vertices.len()).filter(|&v| v != flow.origin && v != flow.destination)
+            {
+                let imbalance = flow.net_outflow(v);
+                if imbalance != 0 {
+                    return Err(SolverError::InvalidSolutionError(
+                        SolutionViolation::FlowConservation {
+                            scenario: solution.id,
+                            origin: name(flow.origin),
+                            destination: name(flow.destination),
+                            vertex: name(v),
+                            imbalance,
+                        },
+                    ));
+                }
+            }
+        }
+
+        for (s, t) in solution.arc_loads.indices() {
+            let commodity_loads = solution
+                .commodity_flows
+                .iter()
+                .map(|flow| flow.arc_load(s, t))
+                .sum::<usize>();
+            let mut arc_load = *solution.arc_loads.get(s, t);
+            if self.penalty_arcs.contains(&(s, t)) {
+                arc_load += *solution.unmet_demand.get(s, t);
+            }
+            if commodity_loads != arc_load {
+                return Err(SolverError::InvalidSolutionError(
+                    SolutionViolation::CommodityLoads {
+                        scenario: solution.id,
+                        arc: (name(s), name(t)),
+                        commodity_loads,
+                        arc_load,
+                    },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Slack of each scenario, i.e. by how much its fixed arc loads fall short of the
+    /// consistent flow, which is the maximum load of each fixed arc over all scenarios.
+    fn recompute_slack(&self, solutions: &[ScenarioSolution]) -> Vec<usize> {
+        let consistent_flow = self
+            .fixed_arcs
+            .iter()
+            .map(|&(a_0, a_1)| {
+                solutions
+                    .iter()
+                    .map(|solution| *solution.arc_loads.get(a_0, a_1))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum::<usize>();
+        solutions
+            .iter()
+            .map(|solution| {
+                consistent_flow
+                    - self
+                        .fixed_arcs
+                        .iter()
+                        .map(|&(a_0, a_1)| *solution.arc_loads.get(a_0, a_1))
+                        .sum::<usize>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::options::SlackFunction;
+
+    /// A triangle of stations a, b and c, solved greedily. Both scenarios send supply from a to c,
+    /// 1 unit and 2 units, over the fixed arc (a,b) and the arc (b,c), which shares its capacity
+    /// with (c,b). Scenario 1 limits the capacity of (c,a), and a penalty arc leads from a to c.
+    pub(crate) fn solved_network() -> Network {
+        let mut network: Network = serde_json::from_value(json!({
+            "vertices": [
+                { "name": "a", "x": 0.0, "y": 0.0 },
+                { "name": "b", "x": 1.0, "y": 0.0, "capacity": 5 },
+                { "name": "c", "x": 1.0, "y": 1.0 },
+            ],
+            "capacities": [[0, 2, 0], [0, 0, 2], [2, 1, 0]],
+            "costs": [[0, 1, 0], [0, 0, 1], [3, 1, 0]],
+            "balances": [
+                [[0, 0, 1], [0, 0, 0], [0, 0, 0]],
+                [[0, 0, 2], [0, 0, 0], [0, 0, 0]],
+            ],
+            "fixed_arcs": [[0, 1]],
+            "capacity_overrides": [[], [[2, 0, 1]]],
+            "arc_groups": [{ "arcs": [[1, 2], [2, 1]], "capacity": 3 }],
+            "baseline": null,
+            "solutions": null,
+        }))
+        .unwrap();
+        network.options = Options {
+            remainder_solve_method: RemainderSolveMethod::Greedy,
+            ..Options::default()
+        };
+        network.add_penalty_arcs(None, &[]).unwrap();
+        network.preprocess().unwrap();
+        network.solve().unwrap();
+        network
+    }
+
+    fn violation(network: &Network) -> SolutionViolation {
+        match network.validate_solution() {
+            Err(SolverError::InvalidSolutionError(violation)) => violation,
+            result => panic!("expected a violation, got {result:?}"),
+        }
+    }
+
+    /// The solution of scenario `lambda`, which the greedy does not necessarily put in order.
+    fn solution(network: &mut Network, lambda: usize) -> &mut ScenarioSolution {
+        network
+            .solutions
+            .as_mut()
+            .unwrap()
+            .iter_mut()
+            .find(|solution| solution.id == lambda)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_validate_solution() {
+        solved_network().validate_solution().unwrap();
+    }
+
+    #[test]
+    fn test_residual_vertex_capacities() {
+        let network = solved_network();
+        assert_eq!(
+            vec![None, Some(5), None],
+            network.residual_vertex_capacities(None)
+        );
+        let solutions = network.solutions.as_ref().unwrap();
+        let solution = solutions.iter().find(|solution| solution.id == 1).unwrap();
+        assert_eq!(
+            vec![None, Some(3), None],
+            network.residual_vertex_capacities(Some(&solution.arc_loads))
+        );
+    }
+
+    #[test]
+    fn test_cost_metrics() {
+        let mut network = solved_network();
+        let km = Matrix::from_elements(&[0, 5, 0, 0, 0, 2, 1, 1, 0], 3, 3);
+        network.cost_metrics.insert("km".to_string(), km.clone());
+
+        // the maximum over both scenarios, which use (a,b) and (b,c) once and twice
+        assert_eq!(
+            vec![("costs".to_string(), 4), ("km".to_string(), 14)],
+            network.metric_costs().unwrap()
+        );
+
+        network.options.routing_metric = "km".to_string();
+        assert_eq!(&km, network.routing_costs());
+        assert_eq!(vec![(&km, 1.0)], network.objective_metrics());
+
+        network.options.objective_fn = ObjectiveFunction::Weighted;
+        network.options.objective_weights = vec![2.0, 0.5];
+        assert_eq!(
+            vec![(&network.costs, 2.0), (&km, 0.5)],
+            network.objective_metrics()
+        );
+
+        network.options.objective_fn = ObjectiveFunction::Lexicographic;
+        network.options.objective_metrics = vec!["km".to_string(), "costs".to_string()];
+        network.options.objective_weights = vec![];
+        assert_eq!(
+            vec![(&km, 1.0), (&network.costs, 1.0)],
+            network.objective_metrics()
+        );
+
+        network.options.objective_metrics = vec!["energy".to_string()];
+        assert!(matches!(
+            network.validate_network(),
+            Err(SolverError::NetworkShapeError(e)) if e.contains("unknown cost metric \"energy\"")
+        ));
+        network.options.objective_metrics = vec![];
+        network.cost_metrics.insert(PRIMARY_METRIC.to_string(), km);
+        assert!(matches!(
+            network.validate_network(),
+            Err(SolverError::NetworkShapeError(e)) if e.contains("is reserved")
+        ));
+    }
+
+    #[test]
+    fn test_warm_start_without_solution() {
+        let mut network = solved_network();
+        network.solutions = None;
+        assert!(matches!(
+            network.warm_start_full_ilp(),
+            Err(SolverError::SkippedSolveError)
+        ));
+    }
+
+    #[test]
+    fn test_arc_capacity_violation() {
+        let mut network = solved_network();
+        solution(&mut network, 1).arc_loads.set(2, 0, 2);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::ArcCapacity {
+                scenario: 1,
+                load: 2,
+                capacity: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_shared_capacity_violation() {
+        let mut network = solved_network();
+        network.arc_groups[0].capacity = 1;
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::SharedCapacity {
+                scenario: 1,
+                load: 2,
+                capacity: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_vertex_capacity_violation() {
+        let mut network = solved_network();
+        network.vertices[1].capacity = Some(1);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::VertexCapacity {
+                scenario: 1,
+                load: 2,
+                capacity: 1,
+                ..
+            }
+        ));
+
+        // Loads on penalty arcs count towards the capacity of their endpoints.
+        let mut network = solved_network();
+        network.vertices[2].capacity = Some(2);
+        network.validate_solution().unwrap();
+        solution(&mut network, 1).unmet_demand.set(0, 2, 1);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::VertexCapacity {
+                scenario: 1,
+                load: 3,
+                capacity: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_excessive_arc_load_violation() {
+        let mut network = solved_network();
+        solution(&mut network, 0).arc_loads.set(0, 1, 2);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::ExcessiveArcLoad {
+                scenario: 0,
+                load: 2,
+                supply: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_flow_conservation_violation() {
+        let mut network = solved_network();
+        let flow = &mut solution(&mut network, 0).commodity_flows[0];
+        let (_, _, load) = flow
+            .arc_loads
+            .iter_mut()
+            .find(|(s, t, _)| (*s, *t) == (1, 2))
+            .unwrap();
+        *load += 1;
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::FlowConservation {
+                scenario: 0,
+                imbalance: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_remaining_supply_violation() {
+        let mut network = solved_network();
+        solution(&mut network, 0).supply_remaining.set(0, 2, 2);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::RemainingSupply {
+                scenario: 0,
+                remaining: 2,
+                supply: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_delivered_supply_violation() {
+        let mut network = solved_network();
+        solution(&mut network, 0).supply_remaining.set(0, 2, 1);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::DeliveredSupply {
+                scenario: 0,
+                delivered: 1,
+                expected: 0,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_commodity_loads_violation() {
+        let mut network = solved_network();
+        solution(&mut network, 0).arc_loads.set(2, 0, 1);
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::CommodityLoads {
+                scenario: 0,
+                commodity_loads: 0,
+                arc_load: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_slack_budget_violation() {
+        let mut network = solved_network();
+        network.options.slack_fn = SlackFunction::None;
+        // the greedy ignores the slack budgets, so its solutions are not held to them
+        network.validate_solution().unwrap();
+
+        network.options.remainder_solve_method = RemainderSolveMethod::Ilp;
+        assert!(matches!(
+            violation(&network),
+            SolutionViolation::SlackBudget {
+                scenario: 0,
+                slack: 1,
+                budget: 0,
+            }
+        ));
+    }
 }
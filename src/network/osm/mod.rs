@@ -0,0 +1,360 @@
+//! Import of railway infrastructure from OpenStreetMap extracts, either as XML or PBF. Only ways
+//! tagged `railway=rail` and nodes tagged `railway=station` or `railway=halt` are used. Tracks are
+//! contracted into arcs between stations and junctions, where tracks meet or end, and the nodes
+//! between them only contribute to the length of their arc.
+
+mod pbf;
+mod xml;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::File,
+    io::Read,
+};
+
+use crate::{Matrix, Options, Result, SolverError};
+
+use super::{
+    projection::{distance, project, EARTH_RADIUS},
+    Network, Vertex,
+};
+
+/// Size of one cost unit in kilometres of track.
+const COST_QUANTUM: f64 = 0.1;
+/// Maximum distance in kilometres between a station node which is not part of a track and the
+/// track it belongs to.
+const MAX_STATION_DISTANCE: f64 = 1.0;
+
+/// A node or way of an extract, along with its tags.
+enum Element {
+    Node {
+        id: i64,
+        coordinates: (f64, f64),
+        tags: Vec<(String, String)>,
+    },
+    Way {
+        refs: Vec<i64>,
+        tags: Vec<(String, String)>,
+    },
+}
+
+fn tag<'a>(tags: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn error(message: impl std::fmt::Display) -> SolverError {
+    SolverError::NetworkImportError(message.to_string())
+}
+
+/// Passes every node and way of the extract at `path` to `f`.
+fn read(path: &str, f: &mut dyn FnMut(Element)) -> Result<()> {
+    let mut start = [0; 16];
+    let len = File::open(path)?.read(&mut start)?;
+    match pbf::is_pbf(&start[..len]) {
+        true => pbf::read(path, f),
+        false => xml::read(path, f),
+    }
+}
+
+struct Station {
+    id: i64,
+    name: Option<String>,
+    coordinates: (f64, f64),
+}
+
+impl Network {
+    /// Builds a network of the railway infrastructure in the OpenStreetMap extract at `path`,
+    /// either in XML or PBF. Stations become station vertices, and junctions non-station ones.
+    /// Arcs cost the length of their track in kilometres, and each track adds `track_capacity`
+    /// to the capacity of its arc. The network has a single scenario without any demand.
+    pub fn from_osm(options: &Options, path: &str, track_capacity: usize) -> Result<Self> {
+        // nodes precede ways, so their coordinates are only known after a second pass
+        let mut tracks = Vec::new();
+        let mut stations = Vec::new();
+        read(path, &mut |element| match element {
+            Element::Node {
+                id,
+                coordinates,
+                tags,
+            } if matches!(tag(&tags, "railway"), Some("station" | "halt")) => {
+                stations.push(Station {
+                    id,
+                    name: tag(&tags, "name").map(str::to_string),
+                    coordinates,
+                })
+            }
+            Element::Way { refs, tags } if tag(&tags, "railway") == Some("rail") => {
+                tracks.push(refs)
+            }
+            _ => {}
+        })?;
+        let track_nodes = tracks.iter().flatten().copied().collect::<HashSet<_>>();
+        let mut coordinates = HashMap::new();
+        read(path, &mut |element| {
+            if let Element::Node {
+                id, coordinates: c, ..
+            } = element
+            {
+                if track_nodes.contains(&id) {
+                    coordinates.insert(id, c);
+                }
+            }
+        })?;
+
+        let mut neighbours: HashMap<i64, Vec<i64>> = HashMap::new();
+        for track in tracks.iter() {
+            for pair in track.windows(2) {
+                let (u, v) = (pair[0], pair[1]);
+                if u == v || !coordinates.contains_key(&u) || !coordinates.contains_key(&v) {
+                    continue;
+                }
+                neighbours.entry(u).or_default().push(v);
+                neighbours.entry(v).or_default().push(u);
+            }
+        }
+        for adjacent in neighbours.values_mut() {
+            adjacent.sort();
+            adjacent.dedup();
+        }
+        if neighbours.is_empty() {
+            return Err(error("the extract contains no railway tracks"));
+        }
+
+        // stations next to the tracks are moved onto the closest track node, which can only be
+        // one whose latitude differs by less than the maximum distance
+        let mut by_latitude = neighbours
+            .keys()
+            .map(|node| (coordinates[node].0, *node))
+            .collect::<Vec<_>>();
+        by_latitude.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let max_latitude_difference = (MAX_STATION_DISTANCE / EARTH_RADIUS).to_degrees();
+        let mut station_nodes = BTreeMap::new();
+        for station in stations {
+            let latitude = station.coordinates.0;
+            let first =
+                by_latitude.partition_point(|(lat, _)| *lat < latitude - max_latitude_difference);
+            let node = match neighbours.contains_key(&station.id) {
+                true => Some(station.id),
+                false => by_latitude[first..]
+                    .iter()
+                    .take_while(|(lat, _)| *lat <= latitude + max_latitude_difference)
+                    .map(|(_, node)| (distance(station.coordinates, coordinates[node]), *node))
+                    .filter(|(d, _)| *d <= MAX_STATION_DISTANCE)
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, node)| node),
+            };
+            match node {
+                Some(node) if !station_nodes.contains_key(&node) => {
+                    station_nodes.insert(node, station);
+                }
+                _ => log::warn!(
+                    "Station {} is not close to any track of its own and has been skipped.",
+                    station.name.unwrap_or(station.id.to_string())
+                ),
+            }
+        }
+
+        let is_vertex =
+            |node: &i64| station_nodes.contains_key(node) || neighbours[node].len() != 2;
+        let mut vertex_nodes = neighbours
+            .keys()
+            .filter(|node| is_vertex(node))
+            .copied()
+            .collect::<Vec<_>>();
+        vertex_nodes.sort();
+        let indices = vertex_nodes
+            .iter()
+            .enumerate()
+            .map(|(v, node)| (*node, v))
+            .collect::<HashMap<_, _>>();
+
+        // lengths and number of parallel tracks of every arc
+        let mut arcs: BTreeMap<(usize, usize), (f64, usize)> = BTreeMap::new();
+        for &u in vertex_nodes.iter() {
+            for &next in neighbours[&u].iter() {
+                let (mut previous, mut current) = (u, next);
+                let mut length = distance(coordinates[&u], coordinates[&next]);
+                while !is_vertex(&current) {
+                    let next = *neighbours[&current]
+                        .iter()
+                        .find(|node| **node != previous)
+                        .unwrap();
+                    length += distance(coordinates[&current], coordinates[&next]);
+                    (previous, current) = (current, next);
+                }
+                if current == u {
+                    continue;
+                }
+                let (shortest, num_tracks) = arcs
+                    .entry((indices[&u], indices[&current]))
+                    .or_insert((f64::MAX, 0));
+                *shortest = shortest.min(length);
+                *num_tracks += 1;
+            }
+        }
+
+        let len = vertex_nodes.len();
+        let mut capacities = Matrix::filled_with(0, len, len);
+        let mut costs = Matrix::filled_with(0, len, len);
+        for (&(s, t), &(length, num_tracks)) in arcs.iter() {
+            capacities.set(s, t, num_tracks * track_capacity);
+            costs.set(s, t, ((length / COST_QUANTUM).round() as usize).max(1));
+        }
+
+        let (positions, projection) = project(
+            &vertex_nodes
+                .iter()
+                .map(|node| match station_nodes.get(node) {
+                    Some(station) => station.coordinates,
+                    None => coordinates[node],
+                })
+                .collect::<Vec<_>>(),
+        );
+        let mut name_counts = HashMap::new();
+        for station in station_nodes.values() {
+            *name_counts.entry(station.name.as_deref()).or_insert(0) += 1;
+        }
+        let vertices = vertex_nodes
+            .iter()
+            .zip(positions)
+            .map(|(node, (x, y))| Vertex {
+                name: match station_nodes.get(node) {
+                    Some(Station {
+                        name: Some(name), ..
+                    }) if name_counts[&Some(name.as_str())] == 1 => name.clone(),
+                    Some(Station {
+                        id,
+                        name: Some(name),
+                        ..
+                    }) => format!("{name} ({id})"),
+                    Some(Station { id, .. }) => format!("Station {id}"),
+                    None => format!("Junction {node}"),
+                },
+                x,
+                y,
+                is_station: station_nodes.contains_key(node),
+                capacity: None,
+            })
+            .collect();
+
+        Ok(Network {
+            vertices,
+            capacities,
+            costs,
+            cost_metrics: BTreeMap::new(),
+            balances: vec![Matrix::filled_with(0, len, len)],
+            fixed_arcs: vec![],
+            quantum: 1.0,
+            cost_quantum: COST_QUANTUM,
+            capacity_overrides: vec![],
+            arc_groups: vec![],
+            penalty_arcs: vec![],
+            projection: Some(projection),
+            baseline: None,
+            solutions: None,
+            auxiliary_network: None,
+            options: options.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Station A at the end of a track via node 2 to junction 3, which continues via node 4 to
+    /// node 5 and, on a second track via node 8, also to node 5. Halt B lies next to node 5. A
+    /// branch leads from junction 3 via node 7 to node 6. Station C is far from any track, and
+    /// the road between nodes 1 and 6 is no track.
+    const EXTRACT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="50.0" lon="8.0">
+    <tag k="railway" v="station"/>
+    <tag k="name" v="A"/>
+  </node>
+  <node id="2" lat="50.0" lon="8.01"/>
+  <node id="3" lat="50.0" lon="8.02"/>
+  <node id="4" lat="50.0" lon="8.03"/>
+  <node id="5" lat="50.0" lon="8.04"/>
+  <node id="6" lat="50.01" lon="8.02"/>
+  <node id="7" lat="50.005" lon="8.02"/>
+  <node id="8" lat="49.999" lon="8.03"/>
+  <node id="10" lat="50.001" lon="8.04">
+    <tag k="railway" v="halt"/>
+    <tag k="name" v="B"/>
+  </node>
+  <node id="11" lat="51.0" lon="9.0">
+    <tag k="railway" v="station"/>
+    <tag k="name" v="C"/>
+  </node>
+  <way id="100">
+    <nd ref="1"/>
+    <nd ref="2"/>
+    <nd ref="3"/>
+    <nd ref="4"/>
+    <nd ref="5"/>
+    <tag k="railway" v="rail"/>
+  </way>
+  <way id="101">
+    <nd ref="3"/>
+    <nd ref="7"/>
+    <nd ref="6"/>
+    <tag k="railway" v="rail"/>
+  </way>
+  <way id="102">
+    <nd ref="3"/>
+    <nd ref="8"/>
+    <nd ref="5"/>
+    <tag k="railway" v="rail"/>
+  </way>
+  <way id="103">
+    <nd ref="1"/>
+    <nd ref="6"/>
+    <tag k="highway" v="primary"/>
+  </way>
+</osm>
+"#;
+
+    #[test]
+    fn test_from_osm_contracts_tracks() {
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_osm_contraction_{}.osm", std::process::id()));
+        std::fs::write(&filename, EXTRACT).unwrap();
+        let network = Network::from_osm(&Options::default(), filename.to_str().unwrap(), 4);
+        std::fs::remove_file(&filename).unwrap();
+        let network = network.unwrap();
+
+        // nodes 1, 3, 5 and 6 remain, with the halt moved onto node 5
+        assert_eq!(
+            vec![
+                ("A".to_string(), true),
+                ("Junction 3".to_string(), false),
+                ("B".to_string(), true),
+                ("Junction 6".to_string(), false),
+            ],
+            network
+                .vertices
+                .iter()
+                .map(|v| (v.name.clone(), v.is_station))
+                .collect::<Vec<_>>()
+        );
+
+        // 1.43km from A to junction 3 and on to B, where the second track is slightly longer,
+        // and 1.11km on the branch, in units of 0.1km
+        assert_eq!(COST_QUANTUM, network.cost_quantum);
+        assert_eq!(
+            Matrix::from_elements(&[0, 4, 0, 0, 4, 0, 8, 4, 0, 8, 0, 0, 0, 4, 0, 0], 4, 4),
+            network.capacities
+        );
+        assert_eq!(
+            Matrix::from_elements(
+                &[0, 14, 0, 0, 14, 0, 14, 11, 0, 14, 0, 0, 0, 11, 0, 0],
+                4,
+                4
+            ),
+            network.costs
+        );
+        assert_eq!(1, network.balances.len());
+        assert_eq!(0, network.balances[0].sum());
+    }
+}
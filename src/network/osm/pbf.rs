@@ -0,0 +1,297 @@
+//! A minimal reader of the OSM PBF format, which decodes just the nodes and ways along with their
+//! tags. Files consist of blobs, each preceded by its length and a header, which contain blocks of
+//! Protocol Buffers messages.
+
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+};
+
+use flate2::read::ZlibDecoder;
+
+use crate::Result;
+
+use super::{error, Element};
+
+/// Files start with the length of the first blob header, whose first field is its type.
+pub(super) fn is_pbf(start: &[u8]) -> bool {
+    start.len() >= 15 && start[4] == 0x0a && &start[6..15] == b"OSMHeader"
+}
+
+pub(super) fn read(path: &str, f: &mut dyn FnMut(Element)) -> Result<()> {
+    let mut reader = BufReader::new(File::open(path)?);
+    loop {
+        let mut len = [0; 4];
+        match reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+        let header = read_bytes(&mut reader, u32::from_be_bytes(len) as usize)?;
+        let (mut blob_type, mut blob_len) = (&[][..], 0);
+        for (field, value) in fields(&header)? {
+            match (field, value) {
+                (1, Field::Bytes(bytes)) => blob_type = bytes,
+                (3, Field::Varint(len)) => blob_len = len as usize,
+                _ => {}
+            }
+        }
+        let blob = read_bytes(&mut reader, blob_len)?;
+        if blob_type == b"OSMData" {
+            read_block(&blob_data(&blob)?, f)?;
+        }
+    }
+}
+
+fn read_bytes(reader: &mut impl Read, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn blob_data(blob: &[u8]) -> Result<Vec<u8>> {
+    for (field, value) in fields(blob)? {
+        match (field, value) {
+            (1, Field::Bytes(raw)) => return Ok(raw.to_vec()),
+            (3, Field::Bytes(compressed)) => {
+                let mut data = Vec::new();
+                ZlibDecoder::new(compressed).read_to_end(&mut data)?;
+                return Ok(data);
+            }
+            _ => {}
+        }
+    }
+    Err(error(
+        "only uncompressed and zlib-compressed blobs are supported",
+    ))
+}
+
+fn read_block(block: &[u8], f: &mut dyn FnMut(Element)) -> Result<()> {
+    let mut strings = Vec::new();
+    let mut groups = Vec::new();
+    let (mut granularity, mut lat_offset, mut lon_offset) = (100, 0, 0);
+    for (field, value) in fields(block)? {
+        match (field, value) {
+            (1, Field::Bytes(table)) => {
+                for (field, value) in fields(table)? {
+                    if let (1, Field::Bytes(string)) = (field, value) {
+                        strings.push(String::from_utf8_lossy(string).into_owned());
+                    }
+                }
+            }
+            (2, Field::Bytes(group)) => groups.push(group),
+            (17, Field::Varint(value)) => granularity = value as i64,
+            (19, Field::Varint(value)) => lat_offset = value as i64,
+            (20, Field::Varint(value)) => lon_offset = value as i64,
+            _ => {}
+        }
+    }
+    // coordinates are given in multiples of the granularity, in nanodegrees
+    let coordinates = |lat: i64, lon: i64| {
+        (
+            1e-9 * (lat_offset + granularity * lat) as f64,
+            1e-9 * (lon_offset + granularity * lon) as f64,
+        )
+    };
+    let string = |index: u64| {
+        strings
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| error("a string index is out of bounds"))
+    };
+    let tags = |keys: &[u64], values: &[u64]| {
+        keys.iter()
+            .zip(values)
+            .map(|(k, v)| Ok((string(*k)?, string(*v)?)))
+            .collect::<Result<Vec<_>>>()
+    };
+
+    for group in groups {
+        for (field, value) in fields(group)? {
+            let Field::Bytes(message) = value else {
+                continue;
+            };
+            match field {
+                1 => {
+                    let (mut id, mut lat, mut lon) = (0, 0, 0);
+                    let (mut keys, mut values) = (vec![], vec![]);
+                    for (field, value) in fields(message)? {
+                        match field {
+                            1 => id = zigzag(value.varint()?),
+                            2 => keys = value.packed()?,
+                            3 => values = value.packed()?,
+                            8 => lat = zigzag(value.varint()?),
+                            9 => lon = zigzag(value.varint()?),
+                            _ => {}
+                        }
+                    }
+                    f(Element::Node {
+                        id,
+                        coordinates: coordinates(lat, lon),
+                        tags: tags(&keys, &values)?,
+                    });
+                }
+                2 => {
+                    let (mut ids, mut lats, mut lons) = (vec![], vec![], vec![]);
+                    let mut keys_values = vec![];
+                    for (field, value) in fields(message)? {
+                        match field {
+                            1 => ids = delta_decode(&value.packed()?),
+                            8 => lats = delta_decode(&value.packed()?),
+                            9 => lons = delta_decode(&value.packed()?),
+                            10 => keys_values = value.packed()?,
+                            _ => {}
+                        }
+                    }
+                    if lats.len() != ids.len() || lons.len() != ids.len() {
+                        return Err(error("dense nodes lack coordinates"));
+                    }
+                    // keys and values of all nodes, each node's terminated by a 0
+                    let mut keys_values = keys_values.into_iter();
+                    for (i, id) in ids.into_iter().enumerate() {
+                        let mut node_tags = vec![];
+                        while let Some(key) = keys_values.next().filter(|key| *key != 0) {
+                            let value = keys_values
+                                .next()
+                                .ok_or_else(|| error("a tag of dense nodes has no value"))?;
+                            node_tags.push((string(key)?, string(value)?));
+                        }
+                        f(Element::Node {
+                            id,
+                            coordinates: coordinates(lats[i], lons[i]),
+                            tags: node_tags,
+                        });
+                    }
+                }
+                3 => {
+                    let (mut keys, mut values, mut refs) = (vec![], vec![], vec![]);
+                    for (field, value) in fields(message)? {
+                        match field {
+                            2 => keys = value.packed()?,
+                            3 => values = value.packed()?,
+                            8 => refs = delta_decode(&value.packed()?),
+                            _ => {}
+                        }
+                    }
+                    f(Element::Way {
+                        refs,
+                        tags: tags(&keys, &values)?,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A field of a Protocol Buffers message. Fixed-size fields are not used by the format.
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl Field<'_> {
+    fn varint(&self) -> Result<u64> {
+        match self {
+            Field::Varint(value) => Ok(*value),
+            _ => Err(error("expected a varint field")),
+        }
+    }
+
+    /// Values of a repeated field, which may or may not be packed.
+    fn packed(&self) -> Result<Vec<u64>> {
+        match self {
+            Field::Varint(value) => Ok(vec![*value]),
+            Field::Bytes(mut bytes) => {
+                let mut values = Vec::new();
+                while !bytes.is_empty() {
+                    values.push(varint(&mut bytes)?);
+                }
+                Ok(values)
+            }
+            Field::Fixed => Err(error("expected a packed field")),
+        }
+    }
+}
+
+fn fields(mut bytes: &[u8]) -> Result<Vec<(u64, Field<'_>)>> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let key = varint(&mut bytes)?;
+        let value = match key & 0x7 {
+            0 => Field::Varint(varint(&mut bytes)?),
+            2 => {
+                let len = varint(&mut bytes)? as usize;
+                if len > bytes.len() {
+                    return Err(error("a message is truncated"));
+                }
+                let (value, rest) = bytes.split_at(len);
+                bytes = rest;
+                Field::Bytes(value)
+            }
+            wire_type @ (1 | 5) => {
+                let len = if wire_type == 1 { 8 } else { 4 };
+                bytes = bytes
+                    .get(len..)
+                    .ok_or_else(|| error("a message is truncated"))?;
+                Field::Fixed
+            }
+            _ => return Err(error("a message contains an unknown wire type")),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+fn varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| error("a message is truncated"))?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(error("a varint is too long"))
+}
+
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Sums up zigzag-encoded differences.
+fn delta_decode(values: &[u64]) -> Vec<i64> {
+    values
+        .iter()
+        .scan(0, |sum, value| {
+            *sum += zigzag(*value);
+            Some(*sum)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!(vec![0, -1, 1, -2, 2], [0, 1, 2, 3, 4].map(zigzag).to_vec());
+        assert_eq!(vec![5, 3, 10], delta_decode(&[10, 3, 14]));
+    }
+
+    #[test]
+    fn test_fields() {
+        // field 1 = 150, field 2 = "ab"
+        let bytes = [0x08, 0x96, 0x01, 0x12, 0x02, b'a', b'b'];
+        let fields = fields(&bytes).unwrap();
+
+        assert!(matches!(fields[0], (1, Field::Varint(150))));
+        assert!(matches!(fields[1], (2, Field::Bytes(b"ab"))));
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::Result;
+
+use super::{error, Element};
+
+pub(super) fn read(path: &str, f: &mut dyn FnMut(Element)) -> Result<()> {
+    let mut reader = Reader::from_file(path)?;
+    let mut buf = Vec::new();
+    let mut current = None;
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) => {
+                match e.name().as_ref() {
+                    b"node" => {
+                        current = Some(Element::Node {
+                            id: attribute(e, "id")?,
+                            coordinates: (attribute(e, "lat")?, attribute(e, "lon")?),
+                            tags: vec![],
+                        })
+                    }
+                    b"way" => {
+                        current = Some(Element::Way {
+                            refs: vec![],
+                            tags: vec![],
+                        })
+                    }
+                    b"nd" => {
+                        if let Some(Element::Way { refs, .. }) = &mut current {
+                            refs.push(attribute(e, "ref")?);
+                        }
+                    }
+                    b"tag" => {
+                        if let Some(Element::Node { tags, .. } | Element::Way { tags, .. }) =
+                            &mut current
+                        {
+                            tags.push((attribute(e, "k")?, attribute(e, "v")?));
+                        }
+                    }
+                    _ => {}
+                }
+                if matches!(event, Event::Empty(_)) && is_element(e.name().as_ref()) {
+                    f(current.take().unwrap());
+                }
+            }
+            Event::End(e) if is_element(e.name().as_ref()) => {
+                if let Some(element) = current.take() {
+                    f(element);
+                }
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+fn is_element(name: &[u8]) -> bool {
+    matches!(name, b"node" | b"way")
+}
+
+fn attribute<T: FromStr>(e: &BytesStart, key: &str) -> Result<T> {
+    let value = e
+        .try_get_attribute(key)?
+        .ok_or_else(|| error(format!("an element has no attribute {key}")))?
+        .unescape_value()?;
+    value
+        .parse()
+        .map_err(|_| error(format!("{value} is not a valid value of attribute {key}")))
+}
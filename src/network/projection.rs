@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+/// Mean radius of the earth in kilometres.
+pub(super) const EARTH_RADIUS: f64 = 6371.0;
+
+/// Equirectangular projection by which the vertex positions of an imported network were
+/// obtained from pairs of "latitude, longitude", in kilometres east and north of `origin`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    /// "Latitude, longitude" of position (0, 0).
+    pub origin: (f64, f64),
+    /// Latitude at which the projection is true to scale.
+    pub latitude: f64,
+}
+
+impl Projection {
+    /// A projection true to scale at its `origin`, for positions of unknown provenance.
+    pub fn at_origin(origin: (f64, f64)) -> Self {
+        Projection {
+            origin,
+            latitude: origin.0,
+        }
+    }
+
+    /// Position in kilometres of a pair of "latitude, longitude".
+    pub fn project(&self, (lat, lon): (f64, f64)) -> (f64, f64) {
+        let (lat_0, lon_0) = self.origin;
+        let scale = self.latitude.to_radians().cos();
+        (
+            EARTH_RADIUS * (lon - lon_0).to_radians() * scale,
+            EARTH_RADIUS * (lat - lat_0).to_radians(),
+        )
+    }
+
+    /// Inverse of `project`.
+    pub fn unproject(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (lat_0, lon_0) = self.origin;
+        let scale = self.latitude.to_radians().cos();
+        (
+            lat_0 + (y / EARTH_RADIUS).to_degrees(),
+            lon_0 + (x / (EARTH_RADIUS * scale)).to_degrees(),
+        )
+    }
+}
+
+/// Projects pairs of "latitude, longitude" onto a plane, true to scale at their mean latitude.
+/// Positions are in kilometres, with the smallest coordinates at 0 and y growing northwards.
+pub(crate) fn project(coordinates: &[(f64, f64)]) -> (Vec<(f32, f32)>, Projection) {
+    if coordinates.is_empty() {
+        return (vec![], Projection::at_origin((0.0, 0.0)));
+    }
+    let projection = Projection {
+        origin: (
+            coordinates
+                .iter()
+                .map(|(lat, _)| *lat)
+                .fold(f64::MAX, f64::min),
+            coordinates
+                .iter()
+                .map(|(_, lon)| *lon)
+                .fold(f64::MAX, f64::min),
+        ),
+        latitude: coordinates.iter().map(|(lat, _)| lat).sum::<f64>() / coordinates.len() as f64,
+    };
+    log::info!(
+        "Positions are kilometres east and north of {:?}, true to scale at latitude {}.",
+        projection.origin,
+        projection.latitude
+    );
+    let positions = coordinates
+        .iter()
+        .map(|&coordinates| {
+            let (x, y) = projection.project(coordinates);
+            (x as f32, y as f32)
+        })
+        .collect();
+    (positions, projection)
+}
+
+/// Great-circle distance in kilometres between two pairs of "latitude, longitude".
+pub(crate) fn distance((lat_1, lon_1): (f64, f64), (lat_2, lon_2): (f64, f64)) -> f64 {
+    let (phi_1, phi_2) = (lat_1.to_radians(), lat_2.to_radians());
+    let d_phi = phi_2 - phi_1;
+    let d_lambda = (lon_2 - lon_1).to_radians();
+    let a =
+        (d_phi / 2.0).sin().powi(2) + phi_1.cos() * phi_2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unproject() {
+        let coordinates = [(50.0, 8.0), (50.0, 9.5), (53.5, 10.0), (48.1, 11.6)];
+        let (positions, projection) = project(&coordinates);
+        assert_eq!((48.1, 8.0), projection.origin);
+        for (position, (lat, lon)) in positions.into_iter().zip(coordinates) {
+            let (x, y) = (position.0 as f64, position.1 as f64);
+            let (lat_1, lon_1) = projection.unproject((x, y));
+            assert!((lat - lat_1).abs() < 1e-4 && (lon - lon_1).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! The solver only works on whole numbers. Network files may nevertheless contain fractional
+//! quantities, e.g. trains per hour: all supply-like values (capacities, demand, arc loads) are
+//! divided by the network's `quantum` and all costs by its `cost_quantum` when reading a file,
+//! and multiplied again when writing one.
+
+use serde_json::{Map, Value};
+
+/// Converts the quantities of a network in file units into whole quanta.
+pub(super) fn quantize(network: &mut Value) {
+    let (quantum, cost_quantum) = quanta(network);
+    let mut inexact = false;
+    scale(network, quantum, cost_quantum, &mut |x, q| {
+        let quanta = x / q;
+        if (quanta - quanta.round()).abs() > 1e-6 {
+            inexact = true;
+        }
+        Value::from(quanta.round().max(0.0) as u64)
+    });
+    if inexact {
+        log::warn!(
+            "Some quantities are not a multiple of their quantum ({quantum} for supply, {cost_quantum} for costs) and have been rounded."
+        );
+    }
+}
+
+/// Converts the quantities of a network in whole quanta back into file units.
+pub(super) fn dequantize(network: &mut Value) {
+    let (quantum, cost_quantum) = quanta(network);
+    scale(network, quantum, cost_quantum, &mut |x, q| {
+        // multiples of e.g. 0.1 are not exact in binary, so round away the noise
+        let precision = 10f64.powi(decimals(q));
+        Value::from((x * q * precision).round() / precision)
+    });
+}
+
+/// Number of decimal places of a quantum, at most 9.
+fn decimals(q: f64) -> i32 {
+    (0..9)
+        .find(|&d| {
+            let shifted = q * 10f64.powi(d);
+            (shifted - shifted.round()).abs() < 1e-9 * shifted.max(1.0)
+        })
+        .unwrap_or(9)
+}
+
+fn quanta(network: &Value) -> (f64, f64) {
+    let get = |key| {
+        network
+            .get(key)
+            .and_then(Value::as_f64)
+            .filter(|q| *q > 0.0)
+            .unwrap_or(1.0)
+    };
+    (get("quantum"), get("cost_quantum"))
+}
+
+fn scale(
+    network: &mut Value,
+    quantum: f64,
+    cost_quantum: f64,
+    f: &mut dyn FnMut(f64, f64) -> Value,
+) {
+    let Some(network) = network.as_object_mut() else {
+        return;
+    };
+    for key in ["capacities", "balances"] {
+        scale_numbers(network.get_mut(key), quantum, f);
+    }
+    scale_numbers(network.get_mut("costs"), cost_quantum, f);
+    if let Some(metrics) = network
+        .get_mut("cost_metrics")
+        .and_then(Value::as_object_mut)
+    {
+        for metric in metrics.values_mut() {
+            scale_numbers(Some(metric), cost_quantum, f);
+        }
+    }
+    // triples of "s, t, capacity"
+    for overrides in elements(network, "capacity_overrides") {
+        if let Some(overrides) = overrides.as_array_mut() {
+            for triple in overrides {
+                scale_numbers(triple.get_mut(2), quantum, f);
+            }
+        }
+    }
+    for key in ["arc_groups", "vertices"] {
+        for element in elements(network, key) {
+            scale_numbers(element.get_mut("capacity"), quantum, f);
+        }
+    }
+    for key in ["baseline", "solutions"] {
+        for solution in elements(network, key) {
+            for field in ["slack", "supply_remaining", "arc_loads", "unmet_demand"] {
+                scale_numbers(solution.get_mut(field), quantum, f);
+            }
+            let paths = solution.get_mut("paths").and_then(Value::as_array_mut);
+            for path in paths.into_iter().flatten() {
+                scale_numbers(path.get_mut("amount"), quantum, f);
+            }
+            // triples of "s, t, load" for each OD pair
+            let flows = solution
+                .get_mut("commodity_flows")
+                .and_then(Value::as_array_mut);
+            for flow in flows.into_iter().flatten() {
+                let triples = flow.get_mut("arc_loads").and_then(Value::as_array_mut);
+                for triple in triples.into_iter().flatten() {
+                    scale_numbers(triple.get_mut(2), quantum, f);
+                }
+            }
+        }
+    }
+}
+
+fn elements<'a>(network: &'a mut Map<String, Value>, key: &str) -> &'a mut [Value] {
+    match network.get_mut(key).and_then(Value::as_array_mut) {
+        Some(elements) => elements,
+        None => &mut [],
+    }
+}
+
+/// Applies `f` to every number within `value`, recursing into arrays.
+fn scale_numbers(value: Option<&mut Value>, q: f64, f: &mut dyn FnMut(f64, f64) -> Value) {
+    match value {
+        Some(Value::Array(elements)) => {
+            for element in elements {
+                scale_numbers(Some(element), q, f);
+            }
+        }
+        // whole numbers in units of 1 are already quanta, and may not survive the trip via f64
+        Some(value) if value.is_u64() && q == 1.0 => {}
+        Some(value) => {
+            if let Some(x) = value.as_f64() {
+                *value = f(x, q);
+            }
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{options::RemainderSolveMethod, Network, Options};
+
+    #[test]
+    fn test_solution_round_trip() {
+        // 1.5 units of demand from a to c, of which only 0.5 fit through b
+        let mut file = json!({
+            "vertices": [
+                { "name": "a", "x": 0.0, "y": 0.0 },
+                { "name": "b", "x": 1.0, "y": 0.0 },
+                { "name": "c", "x": 2.0, "y": 0.0 },
+            ],
+            "capacities": [[0, 0.5, 0], [0, 0, 0.5], [0, 0, 0]],
+            "costs": [[0, 1, 0], [0, 0, 1], [0, 0, 0]],
+            "balances": [[[0, 0, 1.5], [0, 0, 0], [0, 0, 0]]],
+            "fixed_arcs": [],
+            "quantum": 0.5,
+            "baseline": null,
+            "solutions": null,
+        });
+        quantize(&mut file);
+        let mut network: Network = serde_json::from_value(file).unwrap();
+        network.options = Options {
+            remainder_solve_method: RemainderSolveMethod::Greedy,
+            ..Options::default()
+        };
+        network.add_penalty_arcs(None, &[]).unwrap();
+        network.preprocess().unwrap();
+        network.solve().unwrap();
+        let unmet = network.solutions.as_ref().unwrap()[0].unmet_demand.sum();
+        assert!(unmet > 0);
+
+        let solved = serde_json::to_value(&network).unwrap();
+        let mut file = solved.clone();
+        dequantize(&mut file);
+        let unmet_file = file["solutions"][0]["unmet_demand"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|row| row.as_array().unwrap())
+            .map(|x| x.as_f64().unwrap())
+            .sum::<f64>();
+        assert_eq!(unmet as f64 * 0.5, unmet_file);
+
+        quantize(&mut file);
+        assert_eq!(solved, file);
+    }
+
+    #[test]
+    fn test_dequantize_without_float_noise() {
+        let mut file = json!({
+            "capacities": [[0, 3], [7, 0]],
+            "costs": [[0, 1], [3, 0]],
+            "balances": [],
+            "quantum": 0.1,
+            "cost_quantum": 0.25,
+        });
+        dequantize(&mut file);
+
+        assert_eq!(json!([[0.0, 0.3], [0.7, 0.0]]), file["capacities"]);
+        assert_eq!(json!([[0.0, 0.25], [0.75, 0.0]]), file["costs"]);
+    }
+}
@@ -1,8 +1,10 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use crate::{
     network::{Network, Vertex},
-    Matrix, Options,
+    DisruptionMethod, Matrix, Options, Result, SolverError,
 };
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
 #[allow(clippy::too_many_arguments)]
 impl Network {
@@ -28,8 +30,15 @@ impl Network {
             vertices: vec![],
             capacities: Matrix::empty(),
             costs: Matrix::empty(),
+            cost_metrics: BTreeMap::new(),
             balances: vec![],
             fixed_arcs: vec![],
+            quantum: 1.0,
+            cost_quantum: 1.0,
+            capacity_overrides: vec![],
+            arc_groups: vec![],
+            penalty_arcs: vec![],
+            projection: None,
             auxiliary_network: None,
             baseline: None,
             solutions: None,
@@ -60,6 +69,7 @@ impl Network {
                 x: rng.gen_range((-100 * num_vertices as i64)..(100 * num_vertices as i64)) as f32,
                 y: rng.gen_range((-100 * num_vertices as i64)..(100 * num_vertices as i64)) as f32,
                 is_station: rng.gen_bool(station_density),
+                capacity: None,
             })
             .collect();
     }
@@ -122,6 +132,156 @@ impl Network {
             .collect();
     }
 
+    /// Replaces the scenarios with disruptions of the first one, each failing some arcs by
+    /// overriding their capacity with 0 on top of the first scenario's own overrides. Failure
+    /// sets are drawn at most once, and those which leave some demand unroutable are skipped.
+    ///
+    /// Routability is checked as in `diagnose_infeasibility`: the demand towards each destination
+    /// has to fit through the network as a whole. Demand towards different destinations may
+    /// still conflict, e.g. on shared arcs or at vertices with limited capacity, so a kept
+    /// scenario is not guaranteed to be solvable.
+    pub fn disrupt_scenarios(
+        &mut self,
+        method: &DisruptionMethod,
+        num_scenarios: usize,
+        num_failures: usize,
+        demand_perturbation: f64,
+    ) -> Result<()> {
+        log::debug!("Disrupting scenarios: method={method}, num_scenarios={num_scenarios}, num_failures={num_failures}, demand_perturbation={demand_perturbation}.");
+        let Some(demand) = self.balances.first().cloned() else {
+            return Err(SolverError::NetworkShapeError(
+                "disruptions require a base demand matrix".to_owned(),
+            ));
+        };
+        if self.balances.len() > 1 {
+            log::warn!(
+                "Network has {} scenarios, only the first is used as base demand.",
+                self.balances.len()
+            );
+        }
+
+        let mut rng = rand::thread_rng();
+        let base_capacities = self.scenario_capacities(0);
+        let arcs: Vec<(usize, usize)> = base_capacities
+            .indices()
+            .filter(|&(s, t)| s != t && *base_capacities.get(s, t) > 0)
+            .collect();
+        if arcs.is_empty() {
+            return Err(SolverError::NetworkShapeError(
+                "disruptions require arcs which can fail".to_owned(),
+            ));
+        }
+        if !matches!(method, DisruptionMethod::SingleArc)
+            && (num_failures == 0 || num_failures > arcs.len())
+        {
+            return Err(SolverError::NetworkShapeError(format!(
+                "cannot fail {num_failures} arcs per scenario, the network has {} arcs",
+                arcs.len()
+            )));
+        }
+        let base_overrides = self.capacity_overrides.first().cloned().unwrap_or_default();
+
+        // N-1 fails every arc once, however many scenarios that makes
+        let (num_scenarios, max_attempts) = match method {
+            DisruptionMethod::SingleArc => (arcs.len(), arcs.len()),
+            _ => (num_scenarios, 100 * num_scenarios),
+        };
+
+        // the candidate scenario is checked on its own, on a network without solutions
+        let mut candidate = Network {
+            balances: vec![],
+            capacity_overrides: vec![],
+            baseline: None,
+            solutions: None,
+            auxiliary_network: None,
+            ..self.clone()
+        };
+        let mut drawn = BTreeSet::new();
+        let mut balances = vec![];
+        let mut capacity_overrides = vec![];
+        for attempt in 0..max_attempts {
+            if balances.len() == num_scenarios {
+                break;
+            }
+
+            let mut failures: Vec<(usize, usize)> = match method {
+                DisruptionMethod::SingleArc => vec![arcs[attempt]],
+                DisruptionMethod::KRandom => arcs
+                    .choose_multiple(&mut rng, num_failures)
+                    .copied()
+                    .collect(),
+                DisruptionMethod::Clustered => {
+                    let center = &self.vertices[rng.gen_range(0..self.vertices.len())];
+                    let distance = |&(s, t): &(usize, usize)| {
+                        let x = (self.vertices[s].x + self.vertices[t].x) / 2.0 - center.x;
+                        let y = (self.vertices[s].y + self.vertices[t].y) / 2.0 - center.y;
+                        x * x + y * y
+                    };
+                    let mut closest = arcs.clone();
+                    closest.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+                    closest.truncate(num_failures);
+                    closest
+                }
+            };
+            failures.sort();
+            if !drawn.insert(failures.clone()) {
+                continue;
+            }
+
+            let overrides = base_overrides
+                .iter()
+                .filter(|(s, t, _)| !failures.contains(&(*s, *t)))
+                .copied()
+                .chain(failures.iter().map(|&(s, t)| (s, t, 0)))
+                .collect::<Vec<_>>();
+            candidate.balances = vec![self.perturb_demand(&demand, demand_perturbation)];
+            candidate.capacity_overrides = vec![overrides];
+
+            let diagnosis = candidate.diagnose_infeasibility(0);
+            if let Some(bottleneck) = diagnosis.bottlenecks.first() {
+                log::debug!(
+                    "Skipped failure of {:?}, because it makes ({}->{}) unroutable.",
+                    failures,
+                    bottleneck.origins.join(", "),
+                    bottleneck.destination
+                );
+                continue;
+            }
+
+            balances.append(&mut candidate.balances);
+            capacity_overrides.append(&mut candidate.capacity_overrides);
+        }
+
+        if balances.len() < num_scenarios {
+            log::warn!(
+                "Only {} of {} disruption scenarios keep all demand routable.",
+                balances.len(),
+                num_scenarios
+            );
+        }
+
+        self.balances = balances;
+        self.capacity_overrides = capacity_overrides;
+        self.baseline = None;
+        self.solutions = None;
+        Ok(())
+    }
+
+    fn perturb_demand(&self, demand: &Matrix<usize>, demand_perturbation: f64) -> Matrix<usize> {
+        if demand_perturbation <= 0.0 {
+            return demand.clone();
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut perturbed = demand.clone();
+        for (s, t) in demand.indices().filter(|&(s, t)| *demand.get(s, t) > 0) {
+            let factor =
+                rng.gen_range((1.0 - demand_perturbation).max(0.0)..=1.0 + demand_perturbation);
+            perturbed.set(s, t, (*demand.get(s, t) as f64 * factor).round() as usize);
+        }
+        perturbed
+    }
+
     pub fn randomize_fixed_arcs(
         &mut self,
         num_fixed_arcs: usize,
@@ -186,3 +346,89 @@ impl Network {
         matrix
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Demand from a and from b towards c, over the arcs (a,b), (b,c), (a,c) and (c,a) with a
+    /// capacity of 1 each. The first scenario raises the capacity of (c,a).
+    fn setup() -> Network {
+        serde_json::from_value(json!({
+            "vertices": [
+                { "name": "a", "x": 0.0, "y": 0.0 },
+                { "name": "b", "x": 1.0, "y": 0.0 },
+                { "name": "c", "x": 1.0, "y": 1.0 },
+            ],
+            "capacities": [[0, 1, 1], [0, 0, 1], [1, 0, 0]],
+            "costs": [[0, 1, 1], [0, 0, 1], [1, 0, 0]],
+            "balances": [[[0, 0, 1], [0, 0, 1], [0, 0, 0]]],
+            "fixed_arcs": [],
+            "capacity_overrides": [[[2, 0, 3]]],
+            "baseline": null,
+            "solutions": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_disrupt_n_minus_1() {
+        let mut network = setup();
+        network
+            .disrupt_scenarios(&DisruptionMethod::SingleArc, 0, 0, 0.0)
+            .unwrap();
+
+        // failing (a,c) or (b,c) leaves only one unit of capacity into c, for two units of
+        // demand which fit one OD pair at a time
+        assert_eq!(
+            vec![vec![(2, 0, 3), (0, 1, 0)], vec![(2, 0, 0)]],
+            network.capacity_overrides
+        );
+        assert_eq!(2, network.balances.len());
+        assert!(network.balances.iter().all(|balance| balance.sum() == 2));
+    }
+
+    #[test]
+    fn test_disrupt_draws_failures_once() {
+        let mut network = setup();
+        network.balances = vec![Matrix::filled_with(0, 3, 3)];
+        network
+            .disrupt_scenarios(&DisruptionMethod::KRandom, 10, 3, 0.0)
+            .unwrap();
+
+        // without demand, every one of the 4 sets of 3 arcs is kept, but only once
+        let failure_sets = network
+            .capacity_overrides
+            .iter()
+            .map(|overrides| {
+                overrides
+                    .iter()
+                    .filter(|(_, _, capacity)| *capacity == 0)
+                    .map(|&(s, t, _)| (s, t))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<BTreeSet<_>>();
+        assert_eq!(4, network.capacity_overrides.len());
+        assert_eq!(4, failure_sets.len());
+        assert!(failure_sets.iter().all(|failures| failures.len() == 3));
+    }
+
+    #[test]
+    fn test_disrupt_empty_network() {
+        let mut network = setup();
+        network.vertices = vec![];
+        network.capacities = Matrix::empty();
+        network.capacity_overrides = vec![];
+        for method in [DisruptionMethod::Clustered, DisruptionMethod::SingleArc] {
+            assert!(network.disrupt_scenarios(&method, 1, 1, 0.0).is_err());
+        }
+
+        let mut network = setup();
+        assert!(network
+            .disrupt_scenarios(&DisruptionMethod::KRandom, 1, 5, 0.0)
+            .is_err());
+        assert_eq!(vec![vec![(2, 0, 3)]], network.capacity_overrides);
+    }
+}
@@ -0,0 +1,325 @@
+//! Machine-readable results of solving a network, for scripts which would otherwise have to parse
+//! the human-readable output. Like the console output, all values are given in quanta.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Options, RemainderSolveMethod, Result, SolverError};
+
+use super::{export::NetworkData, Network, Solution};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SolveStatus {
+    /// All supply has been delivered.
+    Solved,
+    /// Some supply remains, or could only be routed over penalty arcs.
+    Partial,
+    /// Some scenario cannot be routed at all.
+    Infeasible,
+    /// Solving failed for another reason, or has not been attempted.
+    Failed,
+}
+
+#[derive(Serialize, Debug)]
+struct ScenarioReport {
+    id: usize,
+    cost: usize,
+    metric_costs: BTreeMap<String, usize>,
+    slack: usize,
+    slack_budget: usize,
+    supply: usize,
+    delivered: usize,
+    unmet_demand: usize,
+    unmet_demand_pairs: Vec<UnmetDemandReport>,
+}
+
+/// Demand of an OD pair which could only be routed over its penalty arc.
+#[derive(Serialize, Debug)]
+struct UnmetDemandReport {
+    origin: String,
+    destination: String,
+    amount: usize,
+}
+
+#[derive(Serialize, Debug)]
+struct FixedArcReport {
+    from: String,
+    to: String,
+    loads: Vec<usize>,
+    consistent_flow: usize,
+    consistency: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct SolveReport {
+    status: SolveStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    options: Value,
+    /// Includes the timings in milliseconds, if measured.
+    metrics: NetworkData,
+    scenarios: Vec<ScenarioReport>,
+    fixed_arcs: Vec<FixedArcReport>,
+}
+
+/// The options as in config files. The full ILP cannot be selected in them, and is named like
+/// the other remainder solve methods instead of failing to serialize.
+fn options_value(options: &Options) -> Result<Value> {
+    if !matches!(options.remainder_solve_method, RemainderSolveMethod::Ilp) {
+        return Ok(serde_json::to_value(options)?);
+    }
+    let mut value = serde_json::to_value(Options {
+        remainder_solve_method: RemainderSolveMethod::None,
+        ..options.clone()
+    })?;
+    value["remainder_solve_method"] = Value::from("ilp");
+    Ok(value)
+}
+
+impl Network {
+    fn solve_status(&self, error: Option<&SolverError>) -> SolveStatus {
+        match error {
+            Some(
+                SolverError::InfeasibleScenarioError(_)
+                | SolverError::NoFeasibleFlowError(_)
+                | SolverError::GurobiSolutionError(_),
+            ) => return SolveStatus::Infeasible,
+            Some(_) => return SolveStatus::Failed,
+            None => {}
+        }
+        let Some(solutions) = &self.solutions else {
+            return SolveStatus::Failed;
+        };
+        match solutions.iter().all(|solution| {
+            solution.supply_delivered(self.balances[solution.id].sum())
+                == self.balances[solution.id].sum()
+        }) {
+            true => SolveStatus::Solved,
+            false => SolveStatus::Partial,
+        }
+    }
+
+    /// The result of solving the network as JSON: its status, the options used, all metrics of
+    /// the CSV export, the cost, slack, delivered supply and unmet demand of every scenario, and
+    /// the consistent flows on the fixed arcs. Timings are given in milliseconds. If solving
+    /// failed with `error`, the status and error say why, along with whatever has been solved so
+    /// far.
+    pub fn result_json(
+        &self,
+        time_preprocess: Option<usize>,
+        time_solve: Option<usize>,
+        error: Option<&SolverError>,
+    ) -> Result<String> {
+        let solutions = self.sorted_solutions().unwrap_or_default();
+        let slack_budgets = self.options.slack_fn.apply(&self.balances);
+        let scenarios = solutions
+            .iter()
+            .map(|solution| ScenarioReport {
+                id: solution.id,
+                cost: solution.cost(self.routing_costs()),
+                metric_costs: solution.metric_costs(&self.metrics()).into_iter().collect(),
+                slack: solution.slack,
+                slack_budget: slack_budgets[solution.id],
+                supply: self.balances[solution.id].sum(),
+                delivered: solution.supply_delivered(self.balances[solution.id].sum()),
+                unmet_demand: solution.unmet_demand.sum(),
+                unmet_demand_pairs: solution
+                    .unmet_demand_pairs()
+                    .into_iter()
+                    .map(|(s, t, amount)| UnmetDemandReport {
+                        origin: self.vertices[s].name.clone(),
+                        destination: self.vertices[t].name.clone(),
+                        amount,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let fixed_arcs = match self.solutions.as_deref().filter(|s| !s.is_empty()) {
+            Some(unsorted) => {
+                let consistent_flows = unsorted.consistent_flows(&self.fixed_arcs);
+                self.fixed_arcs
+                    .iter()
+                    .map(|&(s, t)| FixedArcReport {
+                        from: self.vertices[s].name.clone(),
+                        to: self.vertices[t].name.clone(),
+                        loads: solutions
+                            .iter()
+                            .map(|sol| *sol.arc_loads.get(s, t))
+                            .collect(),
+                        consistent_flow: *consistent_flows.get(s, t) as usize,
+                        consistency: unsorted.consistency(&(s, t)),
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        let report = SolveReport {
+            status: self.solve_status(error),
+            error: match (error, &self.solutions) {
+                (Some(error), _) => Some(error.to_string()),
+                (None, None) => Some(SolverError::SkippedSolveError.to_string()),
+                (None, Some(_)) => None,
+            },
+            options: options_value(&self.options)?,
+            metrics: NetworkData::from_network(self, time_preprocess, time_solve),
+            scenarios,
+            fixed_arcs,
+        };
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    /// The arcs of the network as CSV, along with their load in every scenario as "load_λ" and,
+    /// for fixed arcs, the consistent flow and consistency of the solution.
+    pub fn result_csv(&self) -> Result<String> {
+        let solutions = self.sorted_solutions().unwrap_or_default();
+        let consistent_flows = self
+            .solutions
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .map(|unsorted| (unsorted, unsorted.consistent_flows(&self.fixed_arcs)));
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        let mut header = ["from", "to", "capacity", "cost", "fixed"]
+            .map(str::to_string)
+            .to_vec();
+        header.extend(
+            solutions
+                .iter()
+                .map(|solution| format!("load_{}", solution.id)),
+        );
+        if consistent_flows.is_some() {
+            header.extend(["consistent_flow", "consistency"].map(str::to_string));
+        }
+        writer.write_record(&header)?;
+
+        for (s, t) in self.arcs() {
+            let fixed = self.fixed_arcs.contains(&(s, t));
+            let mut record = vec![
+                self.vertices[s].name.clone(),
+                self.vertices[t].name.clone(),
+                self.capacities.get(s, t).to_string(),
+                self.costs.get(s, t).to_string(),
+                fixed.to_string(),
+            ];
+            record.extend(
+                solutions
+                    .iter()
+                    .map(|solution| solution.arc_loads.get(s, t).to_string()),
+            );
+            if let Some((unsorted, consistent_flows)) = &consistent_flows {
+                match fixed {
+                    true => record.extend([
+                        consistent_flows.get(s, t).to_string(),
+                        unsorted.consistency(&(s, t)).to_string(),
+                    ]),
+                    false => record.extend([String::new(), String::new()]),
+                }
+            }
+            writer.write_record(&record)?;
+        }
+        let csv = writer.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8_lossy(&csv).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    #[test]
+    fn test_result_json() {
+        let network = solved_network();
+        let json = network.result_json(Some(3), Some(5), None).unwrap();
+        let report: Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!("solved", report["status"]);
+        assert!(report.get("error").is_none());
+        assert_eq!("greedy", report["options"]["remainder_solve_method"]);
+        assert_eq!(3, report["metrics"]["vertex_count"]);
+        assert_eq!(3, report["metrics"]["time_preprocess"]);
+        assert_eq!(5, report["metrics"]["time_solve"]);
+
+        let scenarios = report["scenarios"].as_array().unwrap();
+        assert_eq!(2, scenarios.len());
+        for (scenario, supply) in scenarios.iter().zip([1, 2]) {
+            assert_eq!(supply, scenario["supply"]);
+            assert_eq!(supply, scenario["delivered"]);
+            assert_eq!(0, scenario["unmet_demand"]);
+            assert_eq!(serde_json::json!([]), scenario["unmet_demand_pairs"]);
+            assert_eq!(2 * supply, scenario["cost"]);
+            assert_eq!(2 * supply, scenario["metric_costs"]["costs"]);
+            assert!(scenario["slack"].is_u64());
+            assert!(scenario["slack_budget"].is_u64());
+        }
+
+        let fixed_arcs = report["fixed_arcs"].as_array().unwrap();
+        assert_eq!(1, fixed_arcs.len());
+        assert_eq!("a", fixed_arcs[0]["from"]);
+        assert_eq!("b", fixed_arcs[0]["to"]);
+        assert_eq!(serde_json::json!([1, 2]), fixed_arcs[0]["loads"]);
+        assert_eq!(1, fixed_arcs[0]["consistent_flow"]);
+        assert_eq!(0.5, fixed_arcs[0]["consistency"]);
+    }
+
+    #[test]
+    fn test_result_json_unmet_demand() {
+        let mut network = solved_network();
+        let solutions = network.solutions.as_mut().unwrap();
+        let solution = solutions
+            .iter_mut()
+            .find(|solution| solution.id == 1)
+            .unwrap();
+        solution.unmet_demand.set(0, 2, 1);
+        let report: Value =
+            serde_json::from_str(&network.result_json(None, None, None).unwrap()).unwrap();
+
+        let scenario = &report["scenarios"][1];
+        assert_eq!(1, scenario["unmet_demand"]);
+        assert_eq!(
+            serde_json::json!([{ "origin": "a", "destination": "c", "amount": 1 }]),
+            scenario["unmet_demand_pairs"]
+        );
+        assert_eq!(
+            serde_json::json!([]),
+            report["scenarios"][0]["unmet_demand_pairs"]
+        );
+    }
+
+    #[test]
+    fn test_result_json_on_failure() {
+        let mut network = solved_network();
+        network.options.remainder_solve_method = RemainderSolveMethod::Ilp;
+        let error = SolverError::NoFeasibleFlowError(1);
+        let report: Value =
+            serde_json::from_str(&network.result_json(None, None, Some(&error)).unwrap()).unwrap();
+        assert_eq!("infeasible", report["status"]);
+        assert_eq!(error.to_string(), report["error"]);
+        assert_eq!("ilp", report["options"]["remainder_solve_method"]);
+
+        network.solutions = None;
+        let report: Value =
+            serde_json::from_str(&network.result_json(None, None, None).unwrap()).unwrap();
+        assert_eq!("failed", report["status"]);
+        assert!(report["scenarios"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_result_csv() {
+        let network = solved_network();
+        let csv = network.result_csv().unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            Some("from,to,capacity,cost,fixed,load_0,load_1,consistent_flow,consistency"),
+            lines.next()
+        );
+        let rows = lines.collect::<Vec<_>>();
+        assert!(rows.contains(&"a,b,2,1,true,1,2,1,0.5"));
+        assert!(rows.contains(&"b,c,2,1,false,1,2,,"));
+    }
+}
@@ -5,12 +5,23 @@ use serde::{Deserialize, Serialize};
 
 use crate::{options::CostFunction, Matrix};
 
+use super::{CommodityFlow, FlowPath};
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ScenarioSolution {
     pub id: usize,
     pub slack: usize,
     pub supply_remaining: Matrix<usize>,
     pub arc_loads: Matrix<usize>,
+    /// Supply which could only be routed over penalty arcs, by OD pair.
+    #[serde(default = "Matrix::empty")]
+    pub unmet_demand: Matrix<usize>,
+    /// Flow of each OD pair, including the parts routed over penalty arcs.
+    #[serde(default)]
+    pub commodity_flows: Vec<CommodityFlow>,
+    /// Paths taken by the supply of each OD pair, along with their amounts.
+    #[serde(default)]
+    pub paths: Vec<FlowPath>,
 }
 
 impl ScenarioSolution {
@@ -20,6 +31,9 @@ impl ScenarioSolution {
             slack: 0,
             supply_remaining: supply.clone(),
             arc_loads: Matrix::filled_with(0, supply.num_rows(), supply.num_columns()),
+            unmet_demand: Matrix::filled_with(0, supply.num_rows(), supply.num_columns()),
+            commodity_flows: vec![],
+            paths: vec![],
         }
     }
 
@@ -27,8 +41,37 @@ impl ScenarioSolution {
         self.arc_loads.hadamard_product(cost_matrix).sum()
     }
 
+    /// Cost of the scenario in each of the given cost metrics.
+    pub(crate) fn metric_costs(&self, metrics: &[(&str, &Matrix<usize>)]) -> Vec<(String, usize)> {
+        metrics
+            .iter()
+            .map(|(name, metric)| (name.to_string(), self.cost(metric)))
+            .collect()
+    }
+
     pub(crate) fn supply_delivered(&self, supply_total: usize) -> usize {
-        supply_total - self.supply_remaining.sum()
+        supply_total - self.supply_remaining.sum() - self.unmet_demand.sum()
+    }
+
+    /// OD pairs with unmet demand, along with the amount.
+    pub(crate) fn unmet_demand_pairs(&self) -> Vec<(usize, usize, usize)> {
+        self.unmet_demand
+            .indices()
+            .map(|(s, t)| (s, t, *self.unmet_demand.get(s, t)))
+            .filter(|(_, _, unmet)| *unmet > 0)
+            .collect()
+    }
+
+    /// Paths which route supply over arc `(s, t)`.
+    pub fn paths_through(&self, s: usize, t: usize) -> impl Iterator<Item = &FlowPath> {
+        self.paths.iter().filter(move |path| path.uses_arc(s, t))
+    }
+
+    /// Paths taken by the supply from `origin` to `destination`.
+    pub fn routes(&self, origin: usize, destination: usize) -> impl Iterator<Item = &FlowPath> {
+        self.paths
+            .iter()
+            .filter(move |path| (path.origin, path.destination) == (origin, destination))
     }
 
     pub(crate) fn normalized_arc_load(&self, fixed_arc: &(usize, usize), f_max: usize) -> f64 {
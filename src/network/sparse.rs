@@ -0,0 +1,434 @@
+//! Sparse JSON network files, meant to be written and edited by hand. Instead of dense matrices,
+//! they list arcs as objects of "from, to, capacity, cost" and each scenario as a list of demands
+//! of "from, to, amount", referencing vertices by name. Any other reference to a vertex, e.g. in
+//! fixed arcs or solutions, uses its name as well.
+//!
+//! Sparse files are converted from and into the dense JSON format as is, i.e. in file units,
+//! before quantities are scaled to whole quanta.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Map, Value};
+
+use crate::{Result, SolverError};
+
+/// Matrices of a solution, along with the key under which their elements are listed.
+const MATRIX_FIELDS: [(&str, &str); 3] = [
+    ("supply_remaining", "amount"),
+    ("arc_loads", "load"),
+    ("unmet_demand", "amount"),
+];
+
+/// Sparse networks list their arcs, whereas dense networks store capacities.
+pub(super) fn is_sparse(network: &Value) -> bool {
+    network.get("arcs").is_some()
+}
+
+/// Converts a sparse network into the dense format.
+pub(super) fn to_dense(network: Value) -> Result<Value> {
+    let Value::Object(mut network) = network else {
+        return Err(error("the network is not a JSON object"));
+    };
+    let names = Names::from_vertices(network.get("vertices"))?;
+    let len = names.names.len();
+
+    let mut capacities = zeros(len);
+    let mut costs = zeros(len);
+    let mut cost_metrics = BTreeMap::new();
+    for arc in take_array(&mut network, "arcs") {
+        let (s, t) = names.arc(&arc)?;
+        capacities[s][t] = number(&arc, "capacity");
+        costs[s][t] = number(&arc, "cost");
+        if let Some(metrics) = arc.get("metrics").and_then(Value::as_object) {
+            for (metric, value) in metrics {
+                cost_metrics
+                    .entry(metric.clone())
+                    .or_insert_with(|| zeros(len))[s][t] = value.clone();
+            }
+        }
+    }
+    network.insert("capacities".to_string(), dense(capacities));
+    network.insert("costs".to_string(), dense(costs));
+    network.insert(
+        "cost_metrics".to_string(),
+        cost_metrics
+            .into_iter()
+            .map(|(metric, matrix)| (metric, dense(matrix)))
+            .collect::<Map<_, _>>()
+            .into(),
+    );
+
+    let balances = take_array(&mut network, "scenarios")
+        .iter()
+        .map(|demands| names.matrix(demands, "amount"))
+        .collect::<Result<Vec<_>>>()?;
+    network.insert("balances".to_string(), balances.into());
+
+    for key in ["fixed_arcs", "penalty_arcs"] {
+        let arcs = names.arcs(network.get(key))?;
+        network.insert(key.to_string(), arcs);
+    }
+    let capacity_overrides = take_array(&mut network, "capacity_overrides")
+        .iter()
+        .map(|overrides| names.triples(overrides, "capacity"))
+        .collect::<Result<Vec<_>>>()?;
+    network.insert("capacity_overrides".to_string(), capacity_overrides.into());
+    for group in objects_mut(network.get_mut("arc_groups")) {
+        let arcs = names.arcs(group.get("arcs"))?;
+        group.insert("arcs".to_string(), arcs);
+    }
+
+    for key in ["baseline", "solutions"] {
+        for solution in objects_mut(network.get_mut(key)) {
+            for (field, amount) in MATRIX_FIELDS {
+                let matrix = names.matrix(get(solution, field), amount)?;
+                solution.insert(field.to_string(), matrix);
+            }
+            for flow in objects_mut(solution.get_mut("commodity_flows")) {
+                names.index_od_pair(flow)?;
+                let triples = names.triples(get(flow, "arc_loads"), "load")?;
+                flow.insert("arc_loads".to_string(), triples);
+            }
+            for path in objects_mut(solution.get_mut("paths")) {
+                names.index_od_pair(path)?;
+                let vertices = get(path, "vertices")
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|v| names.index(v))
+                    .collect::<Result<Vec<_>>>()?;
+                path.insert("vertices".to_string(), vertices.into());
+            }
+        }
+    }
+    Ok(Value::Object(network))
+}
+
+/// Converts a dense network into the sparse format.
+pub(super) fn to_sparse(network: Value) -> Result<Value> {
+    let Value::Object(mut network) = network else {
+        return Err(error("the network is not a JSON object"));
+    };
+    let names = Names::from_vertices(network.get("vertices"))?;
+
+    let capacities = take_matrix(&mut network, "capacities");
+    let costs = take_matrix(&mut network, "costs");
+    let cost_metrics = match network.remove("cost_metrics") {
+        Some(Value::Object(metrics)) => metrics
+            .into_iter()
+            .map(|(metric, matrix)| (metric, rows(matrix)))
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+    let mut arcs = Vec::new();
+    for (s, row) in capacities.iter().enumerate() {
+        for (t, capacity) in row.iter().enumerate() {
+            let cost = entry(&costs, s, t);
+            let metrics = cost_metrics
+                .iter()
+                .map(|(metric, matrix)| (metric.clone(), entry(matrix, s, t)))
+                .collect::<Map<_, _>>();
+            if is_zero(capacity) && is_zero(&cost) && metrics.values().all(is_zero) {
+                continue;
+            }
+            let mut arc = json!({
+                "from": names.names[s],
+                "to": names.names[t],
+                "capacity": capacity,
+                "cost": cost,
+            });
+            if !metrics.is_empty() {
+                arc["metrics"] = metrics.into();
+            }
+            arcs.push(arc);
+        }
+    }
+    network.insert("arcs".to_string(), arcs.into());
+
+    let scenarios = take_array(&mut network, "balances")
+        .into_iter()
+        .map(|balance| names.entries(balance, "amount"))
+        .collect::<Vec<_>>();
+    network.insert("scenarios".to_string(), scenarios.into());
+
+    for key in ["fixed_arcs", "penalty_arcs"] {
+        let arcs = names.named_arcs(network.get(key));
+        network.insert(key.to_string(), arcs);
+    }
+    let capacity_overrides = take_array(&mut network, "capacity_overrides")
+        .iter()
+        .map(|overrides| names.named_triples(overrides, "capacity"))
+        .collect::<Vec<_>>();
+    network.insert("capacity_overrides".to_string(), capacity_overrides.into());
+    for group in objects_mut(network.get_mut("arc_groups")) {
+        let arcs = names.named_arcs(group.get("arcs"));
+        group.insert("arcs".to_string(), arcs);
+    }
+
+    for key in ["baseline", "solutions"] {
+        for solution in objects_mut(network.get_mut(key)) {
+            for (field, amount) in MATRIX_FIELDS {
+                let matrix = solution.remove(field).unwrap_or_default();
+                solution.insert(field.to_string(), names.entries(matrix, amount));
+            }
+            for flow in objects_mut(solution.get_mut("commodity_flows")) {
+                names.name_od_pair(flow);
+                let triples = names.named_triples(get(flow, "arc_loads"), "load");
+                flow.insert("arc_loads".to_string(), triples);
+            }
+            for path in objects_mut(solution.get_mut("paths")) {
+                names.name_od_pair(path);
+                let vertices = get(path, "vertices")
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|v| names.name(v))
+                    .collect::<Vec<_>>();
+                path.insert("vertices".to_string(), vertices.into());
+            }
+        }
+    }
+    Ok(Value::Object(network))
+}
+
+/// Vertex names along with the index of each name.
+struct Names {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl Names {
+    fn from_vertices(vertices: Option<&Value>) -> Result<Self> {
+        let names = vertices
+            .and_then(Value::as_array)
+            .ok_or_else(|| error("the network has no list of vertices"))?
+            .iter()
+            .map(|vertex| match vertex.get("name").and_then(Value::as_str) {
+                Some(name) => Ok(name.to_string()),
+                None => Err(error("a vertex has no name")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut indices = HashMap::new();
+        for (v, name) in names.iter().enumerate() {
+            if indices.insert(name.clone(), v).is_some() {
+                return Err(error(&format!(
+                    "the vertex name {name} is used more than once"
+                )));
+            }
+        }
+        Ok(Names { names, indices })
+    }
+
+    fn index(&self, name: &Value) -> Result<usize> {
+        name.as_str()
+            .and_then(|name| self.indices.get(name).copied())
+            .ok_or_else(|| error(&format!("{name} is not the name of a vertex")))
+    }
+
+    /// The name of the vertex at index `v`, or `v` itself if there is no such vertex.
+    fn name(&self, v: &Value) -> Value {
+        match v.as_u64().and_then(|v| self.names.get(v as usize)) {
+            Some(name) => name.as_str().into(),
+            None => v.clone(),
+        }
+    }
+
+    fn index_od_pair(&self, object: &mut Map<String, Value>) -> Result<()> {
+        for key in ["origin", "destination"] {
+            let v = self.index(get(object, key))?;
+            object.insert(key.to_string(), v.into());
+        }
+        Ok(())
+    }
+
+    fn name_od_pair(&self, object: &mut Map<String, Value>) {
+        for key in ["origin", "destination"] {
+            let name = self.name(get(object, key));
+            object.insert(key.to_string(), name);
+        }
+    }
+
+    fn arc(&self, arc: &Value) -> Result<(usize, usize)> {
+        Ok((self.index(&arc["from"])?, self.index(&arc["to"])?))
+    }
+
+    /// Objects of "from, to" to pairs of "s, t".
+    fn arcs(&self, arcs: Option<&Value>) -> Result<Value> {
+        Ok(arcs
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|arc| self.arc(arc).map(|(s, t)| json!([s, t])))
+            .collect::<Result<Vec<_>>>()?
+            .into())
+    }
+
+    /// Pairs of "s, t" to objects of "from, to".
+    fn named_arcs(&self, arcs: Option<&Value>) -> Value {
+        arcs.and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|arc| json!({ "from": self.name(&arc[0]), "to": self.name(&arc[1]) }))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Objects of "from, to, `key`" to triples of "s, t, `key`".
+    fn triples(&self, entries: &Value, key: &str) -> Result<Value> {
+        Ok(entries
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|entry| {
+                let (s, t) = self.arc(entry)?;
+                Ok(json!([s, t, number(entry, key)]))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into())
+    }
+
+    /// Triples of "s, t, `key`" to objects of "from, to, `key`".
+    fn named_triples(&self, triples: &Value, key: &str) -> Value {
+        triples
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|triple| {
+                let mut entry =
+                    json!({ "from": self.name(&triple[0]), "to": self.name(&triple[1]) });
+                entry[key] = triple[2].clone();
+                entry
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Objects of "from, to, `key`" to a dense matrix.
+    fn matrix(&self, entries: &Value, key: &str) -> Result<Value> {
+        let mut matrix = zeros(self.names.len());
+        for entry in entries.as_array().into_iter().flatten() {
+            let (s, t) = self.arc(entry)?;
+            matrix[s][t] = number(entry, key);
+        }
+        Ok(dense(matrix))
+    }
+
+    /// A dense matrix to objects of "from, to, `key`" for each non-zero element.
+    fn entries(&self, matrix: Value, key: &str) -> Value {
+        let mut entries = Vec::new();
+        for (s, row) in rows(matrix).into_iter().enumerate() {
+            for (t, element) in row.into_iter().enumerate() {
+                if is_zero(&element) {
+                    continue;
+                }
+                let mut entry = json!({ "from": self.names[s], "to": self.names[t] });
+                entry[key] = element;
+                entries.push(entry);
+            }
+        }
+        entries.into()
+    }
+}
+
+fn error(message: &str) -> SolverError {
+    SolverError::SparseNetworkFormatError(message.to_string())
+}
+
+fn zeros(len: usize) -> Vec<Vec<Value>> {
+    vec![vec![Value::from(0); len]; len]
+}
+
+fn dense(matrix: Vec<Vec<Value>>) -> Value {
+    Value::Array(matrix.into_iter().map(Value::Array).collect())
+}
+
+fn rows(matrix: Value) -> Vec<Vec<Value>> {
+    match matrix {
+        Value::Array(rows) => rows
+            .into_iter()
+            .map(|row| match row {
+                Value::Array(row) => row,
+                _ => vec![],
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn entry(matrix: &[Vec<Value>], s: usize, t: usize) -> Value {
+    matrix
+        .get(s)
+        .and_then(|row| row.get(t))
+        .cloned()
+        .unwrap_or(Value::from(0))
+}
+
+/// The number under `key`, which defaults to 0.
+fn number(object: &Value, key: &str) -> Value {
+    match object.get(key) {
+        Some(value) if value.is_number() => value.clone(),
+        _ => Value::from(0),
+    }
+}
+
+fn is_zero(value: &Value) -> bool {
+    value.as_f64().is_none_or(|x| x == 0.0)
+}
+
+fn take_array(network: &mut Map<String, Value>, key: &str) -> Vec<Value> {
+    match network.remove(key) {
+        Some(Value::Array(elements)) => elements,
+        _ => vec![],
+    }
+}
+
+fn take_matrix(network: &mut Map<String, Value>, key: &str) -> Vec<Vec<Value>> {
+    network.remove(key).map(rows).unwrap_or_default()
+}
+
+fn get<'a>(object: &'a Map<String, Value>, key: &str) -> &'a Value {
+    object.get(key).unwrap_or(&Value::Null)
+}
+
+/// All objects within the array `value`, if it is one.
+fn objects_mut(value: Option<&mut Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    value
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_round_trip() {
+        let dense = json!({
+            "vertices": [{ "name": "a" }, { "name": "b" }],
+            "capacities": [[0, 2.5], [0, 0]],
+            "costs": [[0, 3], [1, 0]],
+            "cost_metrics": {},
+            "balances": [[[0, 2], [0, 0]]],
+            "fixed_arcs": [[0, 1]],
+            "capacity_overrides": [[[0, 1, 1]]],
+            "arc_groups": [],
+            "penalty_arcs": [],
+        });
+        let sparse = to_sparse(dense.clone()).unwrap();
+
+        assert_eq!(
+            json!([
+                { "from": "a", "to": "b", "capacity": 2.5, "cost": 3 },
+                { "from": "b", "to": "a", "capacity": 0, "cost": 1 },
+            ]),
+            sparse["arcs"]
+        );
+        assert_eq!(
+            json!([[{ "from": "a", "to": "b", "amount": 2 }]]),
+            sparse["scenarios"]
+        );
+        assert_eq!(dense, to_dense(sparse).unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+//! Headless rendering of networks as SVG, drawn like in the GUI but without needing a display.
+
+use quick_xml::escape::escape;
+
+use crate::{ArcGeometry, Matrix, Result, SolverError};
+
+use super::Network;
+
+const BACKGROUND_COLOR: &str = "white";
+const BASELINE_COLOR: &str = "black";
+const HIGHLIGHT_COLOR: &str = "teal";
+const VERTEX_RADIUS: f32 = 20.;
+
+/// Loads by which the opacity of arcs is shaded in SVG renderings.
+#[derive(Debug, Clone, Copy)]
+pub enum LoadShading {
+    /// All arcs are drawn opaque.
+    None,
+    /// Arc loads of the solution of the given scenario.
+    Scenario(usize),
+    /// Consistent flows of the solution on the fixed arcs.
+    ConsistentFlows,
+}
+
+impl Network {
+    /// Renders the network as an SVG image, `width` wide. Arcs are as wide as in the GUI and
+    /// fixed arcs highlighted. Unless `shading` is `None`, arcs are as opaque as their share of
+    /// the largest of the chosen loads.
+    pub fn to_svg(&self, filename: &str, width: f32, shading: LoadShading) -> Result<()> {
+        let loads = self.shading_loads(shading)?;
+        let load_max = loads
+            .as_ref()
+            .map_or(0, |loads| *loads.elements().max().unwrap_or(&0));
+        let cap_max = self.capacities.max() as f32;
+        let vertices = self.normalize_vertex_positions(width);
+        let height = vertices.iter().map(|v| v.y).fold(0., f32::max);
+        let margin = 2. * VERTEX_RADIUS;
+        // the GUI's y axis points upwards, unlike SVG's
+        let point = |(x, y): (f32, f32)| format!("{:.2},{:.2}", x + margin, height - y + margin);
+
+        let mut svg = vec![
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+                width + 2. * margin,
+                height + 2. * margin
+            ),
+            format!("  <rect width=\"100%\" height=\"100%\" fill=\"{BACKGROUND_COLOR}\"/>"),
+        ];
+
+        // Both directions of a single track share one line, as in the GUI
+        let tracks = self.tracks();
+        let mut arcs = Vec::new();
+        for (s, t) in self.capacities.indices() {
+            let track = tracks.iter().find(|track| track.contains(s, t));
+            if track.is_some_and(|track| track.arcs[0] != (s, t)) {
+                continue;
+            }
+            let capacity = match self.arc_groups.iter().find(|g| g.contains(s, t)) {
+                Some(group) => (*self.capacities.get(s, t)).min(group.capacity),
+                None => *self.capacities.get(s, t),
+            };
+            if capacity == 0 {
+                continue;
+            }
+            let load = loads.as_ref().map(|loads| match track {
+                Some(_) => *loads.get(s, t) + *loads.get(t, s),
+                None => *loads.get(s, t),
+            });
+            let opacity = match load {
+                Some(load) if load_max > 0 => 0.1 + 0.9 * (load as f32 / load_max as f32),
+                Some(_) => 0.1,
+                None => 1.,
+            };
+            let fixed = self.fixed_arcs.contains(&(s, t));
+            let geometry = ArcGeometry {
+                s_pos: (vertices[s].x, vertices[s].y),
+                t_pos: (vertices[t].x, vertices[t].y),
+                bidirectional: track.is_some(),
+            };
+            arcs.push((fixed, geometry, capacity, opacity));
+        }
+        // fixed arcs are drawn on top
+        arcs.sort_by_key(|(fixed, ..)| *fixed);
+
+        for (fixed, geometry, capacity, opacity) in arcs {
+            let color = if fixed {
+                HIGHLIGHT_COLOR
+            } else {
+                BASELINE_COLOR
+            };
+            let line_width = ArcGeometry::line_width(capacity, cap_max);
+            svg.push(format!(
+                "  <g stroke=\"{color}\" fill=\"{color}\" opacity=\"{opacity:.3}\" stroke-linejoin=\"round\">"
+            ));
+            svg.push(format!(
+                "    <path d=\"M {} Q {} {}\" fill=\"none\" stroke-width=\"{line_width:.2}\"/>",
+                point(geometry.s_pos),
+                point(geometry.arc_point()),
+                point(geometry.t_pos),
+            ));
+            let arrows = match geometry.bidirectional {
+                true => vec![geometry, geometry.reversed()],
+                false => vec![geometry],
+            };
+            for arrow in arrows {
+                svg.push(format!(
+                    "    <polygon points=\"{}\" stroke-width=\"{:.2}\"/>",
+                    arrow.arrow_points(line_width.max(6.)).map(point).join(" "),
+                    (0.5 * line_width).max(6.)
+                ));
+            }
+            svg.push("  </g>".to_string());
+        }
+
+        for (v, vertex) in vertices.iter().enumerate() {
+            let (x, y) = (vertex.x + margin, height - vertex.y + margin);
+            svg.push(format!(
+                "  <circle cx=\"{x:.2}\" cy=\"{y:.2}\" r=\"{VERTEX_RADIUS}\" fill=\"{BACKGROUND_COLOR}\" stroke=\"{BASELINE_COLOR}\"/>"
+            ));
+            svg.push(format!(
+                "  <text x=\"{x:.2}\" y=\"{y:.2}\" font-size=\"6\" text-anchor=\"middle\" dominant-baseline=\"middle\">{} ({v})</text>",
+                escape(&self.vertices[v].name)
+            ));
+        }
+        svg.push("</svg>".to_string());
+
+        let svg_str = svg.join("\n");
+        log::debug!("Writing\n{svg_str}\nto {filename}");
+        std::fs::write(filename, svg_str)?;
+        Ok(())
+    }
+
+    fn shading_loads(&self, shading: LoadShading) -> Result<Option<Matrix<usize>>> {
+        match shading {
+            LoadShading::None => Ok(None),
+            LoadShading::Scenario(lambda) => {
+                match self.sorted_solutions()?.iter().find(|s| s.id == lambda) {
+                    Some(solution) => Ok(Some(solution.arc_loads.clone())),
+                    None => Err(SolverError::NetworkShapeError(format!(
+                        "Attempted to render the loads of scenario {lambda}, but it has no solution."
+                    ))),
+                }
+            }
+            LoadShading::ConsistentFlows => Ok(Some(self.consistent_flow_loads()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    fn render(network: &Network, shading: LoadShading) -> Result<String> {
+        let filename = std::env::temp_dir().join(format!("robmcf_svg_{}.svg", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        network.to_svg(filename, 200., shading)?;
+        let svg = std::fs::read_to_string(filename)?;
+        std::fs::remove_file(filename)?;
+        Ok(svg)
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let network = solved_network();
+
+        // the fixed arc carries the largest consistent flow, every other arc none at all
+        let svg = render(&network, LoadShading::ConsistentFlows).unwrap();
+        let groups = svg
+            .lines()
+            .filter(|line| line.trim_start().starts_with("<g "))
+            .collect::<Vec<_>>();
+        let fixed = groups
+            .iter()
+            .filter(|line| line.contains("stroke=\"teal\""))
+            .collect::<Vec<_>>();
+        assert_eq!(1, fixed.len());
+        assert!(fixed[0].contains("opacity=\"1.000\""));
+        assert!(groups
+            .iter()
+            .filter(|line| !line.contains("stroke=\"teal\""))
+            .all(|line| line.contains("stroke=\"black\"") && line.contains("opacity=\"0.100\"")));
+
+        assert!(render(&network, LoadShading::Scenario(2)).is_err());
+    }
+}
@@ -1,25 +1,158 @@
-use super::{Network, Vertex};
-use crate::Result;
+use super::{export::NetworkData, Network, ScenarioSolution, Solution, Vertex};
+use crate::{options::LatexFigure, Matrix, Result, SolverError};
+
+const FIGURE_START: &str = "\\begin{figure}[t]
+	            \\centering";
+const FIGURE_END: &str = "	        \\caption{TODO.}
+	        \\label{fig:TODO}
+        \\end{figure}";
+const PICTURE_START: &str = "                \\resizebox{\\textwidth}{!}{%
+	            \\begin{tikzpicture}[>=stealth, auto, node distance=2cm, thick]";
+const PICTURE_END: &str = "\\end{tikzpicture}
+            }%";
+
+/// Thickest line in points with which arc loads are drawn, in addition to the thinnest.
+const LOAD_LINE_WIDTH: f64 = 3.0;
 
 impl Network {
+    /// Writes a tikz figure of the network. Besides the plain network, this can show the loads
+    /// of a solution, either for every scenario in subfigures, as consistent flows on the fixed
+    /// arcs, or as the total loads of the baseline and the solution side by side. Subfigures
+    /// share the scale of their line widths.
     pub fn to_latex(
         &self,
         filename: &str,
+        figure: LatexFigure,
         no_text: bool,
         width: f32,
         mark_stations: bool,
     ) -> Result<()> {
         let vertices = self.normalize_vertex_positions(width);
-        let mut latex = Vec::new();
-
-        latex.push(
-            "\\begin{figure}[t]
-	            \\centering
-                \\resizebox{\\textwidth}{!}{%
-	            \\begin{tikzpicture}[>=stealth, auto, node distance=2cm, thick]"
-                .to_string(),
+        let picture = |loads: Option<&Matrix<usize>>, max_load: usize| {
+            self.tikz_picture(&vertices, no_text, mark_stations, loads, max_load)
+        };
+        let mut latex = vec![FIGURE_START.to_string()];
+
+        match figure {
+            LatexFigure::Network => latex.push(picture(None, 0)),
+            LatexFigure::Scenarios => {
+                let solutions = self.sorted_solutions()?;
+                let max_load = solutions
+                    .iter()
+                    .map(|solution| max_load(&solution.arc_loads))
+                    .max()
+                    .unwrap_or(0);
+                for solution in solutions {
+                    latex.push(subfigure(
+                        &picture(Some(&solution.arc_loads), max_load),
+                        &format!("Scenario {}", solution.id),
+                    ));
+                }
+            }
+            LatexFigure::ConsistentFlows => {
+                let loads = self.consistent_flow_loads()?;
+                latex.push(picture(Some(&loads), max_load(&loads)));
+            }
+            LatexFigure::Baseline => {
+                let (Some(baseline), Some(solutions)) = (&self.baseline, &self.solutions) else {
+                    return Err(SolverError::SkippedSolveError);
+                };
+                let total_loads = |solutions: &[ScenarioSolution]| {
+                    let mut loads =
+                        Matrix::filled_with(0, self.vertices.len(), self.vertices.len());
+                    for solution in solutions.iter() {
+                        loads = loads.add(&solution.arc_loads);
+                    }
+                    loads
+                };
+                let pictures = [("Baseline", baseline), ("Solution", solutions)]
+                    .map(|(name, solutions)| (name, solutions, total_loads(solutions)));
+                let max_load = pictures
+                    .iter()
+                    .map(|(_, _, loads)| max_load(loads))
+                    .max()
+                    .unwrap_or(0);
+                for (name, solutions, loads) in pictures {
+                    latex.push(subfigure(
+                        &picture(Some(&loads), max_load),
+                        &format!(
+                            "{name} ($\\eta = {:.3}$)",
+                            solutions.robustness_coefficient(&self.fixed_arcs)
+                        ),
+                    ));
+                }
+            }
+        }
+
+        latex.push(FIGURE_END.to_string());
+        let latex_str = latex.join("\n");
+
+        log::debug!("Writing\n{latex_str}\nto {filename}");
+        std::fs::write(filename, latex_str)?;
+        Ok(())
+    }
+
+    /// Writes the metrics of the solution, as exported to CSV, as a booktabs table.
+    pub fn to_latex_table(&self, filename: &str) -> Result<()> {
+        if self.solutions.is_none() {
+            return Err(SolverError::SkippedSolveError);
+        }
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(NetworkData::from_network(self, None, None))?;
+        let csv = writer.into_inner().map_err(|e| e.into_error())?;
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        let metrics = reader.headers()?.clone();
+        let values = reader.records().next().transpose()?.unwrap_or_default();
+
+        let mut latex = vec![
+            "\\begin{table}[t]".to_string(),
+            "    \\centering".to_string(),
+            "    \\begin{tabular}{lr}".to_string(),
+            "        \\toprule".to_string(),
+            "        Metric & Value \\\\".to_string(),
+            "        \\midrule".to_string(),
+        ];
+        for (metric, value) in metrics.iter().zip(values.iter()) {
+            if value.is_empty() {
+                continue;
+            }
+            let value = match value.parse::<f64>() {
+                Ok(number) if value.contains('.') => format!("{number:.3}"),
+                _ => escape(value),
+            };
+            latex.push(format!("        {} & {value} \\\\", escape(metric)));
+        }
+        latex.extend(
+            [
+                "        \\bottomrule",
+                "    \\end{tabular}",
+                "    \\caption{TODO.}",
+                "    \\label{tab:TODO}",
+                "\\end{table}",
+            ]
+            .map(str::to_string),
         );
 
+        let latex_str = latex.join("\n");
+        log::debug!("Writing\n{latex_str}\nto {filename}");
+        std::fs::write(filename, latex_str)?;
+        Ok(())
+    }
+
+    /// A tikz picture of the network. Without `loads`, arcs are labelled with their costs and
+    /// capacities. With them, arcs are labelled with and drawn as thick as their loads, and arcs
+    /// without any load are greyed out, with `max_load` drawn thickest. All labels are in units,
+    /// not quanta.
+    fn tikz_picture(
+        &self,
+        vertices: &[Vertex],
+        no_text: bool,
+        mark_stations: bool,
+        loads: Option<&Matrix<usize>>,
+        max_load: usize,
+    ) -> String {
+        let mut latex = vec![PICTURE_START.to_string()];
+
         for (i, vertex) in vertices.iter().enumerate() {
             latex.push(format!(
                 "\\node[circle, draw{}] (v{i}) at ({},{}) {{{}}};",
@@ -30,7 +163,62 @@ impl Network {
                 },
                 vertex.x,
                 vertex.y,
-                if no_text { "" } else { &vertex.name }
+                if no_text {
+                    String::new()
+                } else {
+                    escape(&vertex.name)
+                }
+            ));
+        }
+
+        let amount = |quanta: usize| (quanta as f64 * self.quantum).to_string();
+        let cost =
+            |i: usize, j: usize| (*self.costs.get(i, j) as f64 * self.cost_quantum).to_string();
+
+        // Style and labels above and below of an arc with the given load
+        let load_style = |load: usize, style: String, above: String, below: String| match loads {
+            None => (style, above, below),
+            Some(_) if load == 0 => (
+                format!("draw=gray!40, {style}"),
+                " ".to_string(),
+                " ".to_string(),
+            ),
+            Some(_) => (
+                format!(
+                    "{style}, line width={:.2}pt",
+                    0.4 + LOAD_LINE_WIDTH * load as f64 / max_load as f64
+                ),
+                if no_text {
+                    " ".to_string()
+                } else {
+                    amount(load)
+                },
+                " ".to_string(),
+            ),
+        };
+
+        // Both directions of a single track are drawn as one undirected line
+        let tracks = self.tracks();
+        for track in tracks.iter() {
+            let (i, j) = track.arcs[0];
+            let costs = if self.costs.get(i, j) == self.costs.get(j, i) {
+                cost(i, j)
+            } else {
+                format!("{}/{}", cost(i, j), cost(j, i))
+            };
+            let load = loads.map_or(0, |loads| loads.get(i, j) + loads.get(j, i));
+            let (style, above, below) = load_style(
+                load,
+                "<->".to_string(),
+                if no_text {
+                    " ".to_string()
+                } else {
+                    amount(track.capacity)
+                },
+                if no_text { " ".to_string() } else { costs },
+            );
+            latex.push(format!(
+                "\\draw[{style}] (v{i}) to node[below, sloped] {{\\footnotesize{{${below}$}}}} node[above, sloped] {{\\footnotesize{{${above}$}}}} (v{j});",
             ));
         }
 
@@ -38,31 +226,40 @@ impl Network {
             if *self.capacities.get(i, j) == 0 && !self.fixed_arcs.contains(&(i, j)) {
                 continue;
             }
+            if tracks.iter().any(|track| track.contains(i, j)) {
+                continue;
+            }
+            let capacity = match self.arc_groups.iter().find(|group| group.contains(i, j)) {
+                Some(group) => format!(
+                    "{}\\,({})",
+                    amount(*self.capacities.get(i, j)),
+                    amount(group.capacity)
+                ),
+                None => amount(*self.capacities.get(i, j)),
+            };
+            let (style, above, below) = load_style(
+                loads.map_or(0, |loads| *loads.get(i, j)),
+                format!(
+                    "->{}",
+                    if self.fixed_arcs.contains(&(i, j)) {
+                        ", draw=teal"
+                    } else {
+                        ""
+                    }
+                ),
+                if no_text { " ".to_string() } else { capacity },
+                if no_text { " ".to_string() } else { cost(i, j) },
+            );
             latex.push(format!(
-                "\\draw[->{}] (v{i}) to[bend left=20] node[below, sloped] {{\\footnotesize{{${}$}}}} node[above, sloped] {{\\footnotesize{{${}$}}}} (v{j});",
-                if self.fixed_arcs.contains(&(i, j)) { ", draw=teal" } else { "" },
-                if no_text { " ".to_string() } else { self.costs.get(i, j).to_string() },
-                if no_text { " ".to_string() } else { self.capacities.get(i, j).to_string() },
+                "\\draw[{style}] (v{i}) to[bend left=20] node[below, sloped] {{\\footnotesize{{${below}$}}}} node[above, sloped] {{\\footnotesize{{${above}$}}}} (v{j});",
             ));
         }
 
-        latex.push(
-            "\\end{tikzpicture}
-            }%
-	        \\caption{TODO.}
-	        \\label{fig:TODO}
-        \\end{figure}"
-                .to_string(),
-        );
-
-        let latex_str = latex.join("\n");
-
-        log::debug!("Writing\n{latex_str}\nto {filename}");
-        std::fs::write(filename, latex_str)?;
-        Ok(())
+        latex.push(PICTURE_END.to_string());
+        latex.join("\n")
     }
 
-    fn normalize_vertex_positions(&self, width: f32) -> Vec<Vertex> {
+    pub(super) fn normalize_vertex_positions(&self, width: f32) -> Vec<Vertex> {
         let mut min_x = 0.0;
         let mut max_x = 0.0;
         let mut min_y = 0.0;
@@ -93,6 +290,7 @@ impl Network {
             .map(|v| Vertex {
                 name: v.name.clone(),
                 is_station: v.is_station,
+                capacity: v.capacity,
                 x: if max_x == 0.0 {
                     0.0
                 } else {
@@ -107,3 +305,102 @@ impl Network {
             .collect::<Vec<Vertex>>()
     }
 }
+
+/// Largest of the given loads.
+fn max_load(loads: &Matrix<usize>) -> usize {
+    *loads.elements().max().unwrap_or(&0)
+}
+
+/// Wraps a picture into a subfigure, two of which fit next to each other.
+fn subfigure(picture: &str, caption: &str) -> String {
+    format!(
+        "\\begin{{subfigure}}[b]{{0.48\\textwidth}}\n\\centering\n{picture}\n\\caption{{{caption}}}\n\\end{{subfigure}}\\hfill"
+    )
+}
+
+/// Escapes the special characters of LaTeX in text.
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{c}"),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '\\' => "\\textbackslash{}".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::solved_network;
+
+    fn read_written(name: &str, write: impl FnOnce(&str) -> Result<()>) -> String {
+        let filename =
+            std::env::temp_dir().join(format!("robmcf_latex_{name}_{}.tex", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        write(filename).unwrap();
+        let latex = std::fs::read_to_string(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        latex
+    }
+
+    #[test]
+    fn test_to_latex() {
+        let mut network = solved_network();
+        network.vertices[0].name = "a_1".to_string();
+        // the greedy does not necessarily put its solutions in order
+        network
+            .solutions
+            .as_mut()
+            .unwrap()
+            .sort_by_key(|solution| solution.id);
+        network.baseline = network.solutions.clone();
+        network.baseline.as_mut().unwrap().truncate(1);
+        let figure = |figure: LatexFigure| {
+            read_written(&figure.to_string(), |filename| {
+                network.to_latex(filename, figure, false, 10., true)
+            })
+        };
+
+        let latex = figure(LatexFigure::Network);
+        assert!(latex.contains("{a\\_1};"));
+
+        // scenario 0 only has half the largest load of scenario 1
+        let latex = figure(LatexFigure::Scenarios);
+        assert_eq!(2, latex.matches("\\begin{subfigure}").count());
+        assert!(latex.contains("line width=1.90pt"));
+
+        let latex = figure(LatexFigure::ConsistentFlows);
+        assert!(latex.contains("->, draw=teal, line width=3.40pt"));
+
+        // the baseline has a third of the total load of the solution
+        let latex = figure(LatexFigure::Baseline);
+        assert!(latex.contains("Baseline ($\\eta"));
+        assert!(latex.contains("line width=1.40pt"));
+        assert!(latex.contains("line width=3.40pt"));
+    }
+
+    #[test]
+    fn test_to_latex_table() {
+        let network = solved_network();
+        let latex = read_written("table", |filename| network.to_latex_table(filename));
+        assert!(latex.starts_with("\\begin{table}[t]"));
+        assert!(latex.contains("unmet\\_demand\\_total & 0 \\\\"));
+        assert!(latex.ends_with("\\end{table}"));
+
+        let mut network = solved_network();
+        network.solutions = None;
+        assert!(network.to_latex_table("unused.tex").is_err());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("vertex\\_count", escape("vertex_count"));
+        assert_eq!(
+            "costs=10 50\\% \\textasciitilde{}",
+            escape("costs=10 50% ~")
+        );
+    }
+}
@@ -9,6 +9,9 @@ pub struct Vertex {
     pub y: f32,
     #[serde(default = "default_true")]
     pub is_station: bool,
+    /// Maximum number of supply units that may enter the vertex, i.e. its throughput.
+    #[serde(default)]
+    pub capacity: Option<usize>,
 }
 
 fn default_true() -> bool {
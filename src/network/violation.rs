@@ -0,0 +1,162 @@
+use std::fmt::Display;
+
+/// A way in which a solution fails to be a valid robust flow. Vertices are given by name.
+#[derive(Debug, Clone)]
+pub enum SolutionViolation {
+    ArcCapacity {
+        scenario: usize,
+        arc: (String, String),
+        load: usize,
+        capacity: usize,
+    },
+    SharedCapacity {
+        scenario: usize,
+        arcs: Vec<(String, String)>,
+        load: usize,
+        capacity: usize,
+    },
+    VertexCapacity {
+        scenario: usize,
+        vertex: String,
+        load: usize,
+        capacity: usize,
+    },
+    ExcessiveArcLoad {
+        scenario: usize,
+        arc: (String, String),
+        load: usize,
+        supply: usize,
+    },
+    FlowConservation {
+        scenario: usize,
+        origin: String,
+        destination: String,
+        vertex: String,
+        /// Outflow minus inflow
+        imbalance: i64,
+    },
+    RemainingSupply {
+        scenario: usize,
+        origin: String,
+        destination: String,
+        remaining: usize,
+        supply: usize,
+    },
+    DeliveredSupply {
+        scenario: usize,
+        origin: String,
+        destination: String,
+        delivered: usize,
+        expected: usize,
+    },
+    CommodityLoads {
+        scenario: usize,
+        arc: (String, String),
+        commodity_loads: usize,
+        arc_load: usize,
+    },
+    SlackBudget {
+        scenario: usize,
+        slack: usize,
+        budget: usize,
+    },
+}
+
+impl Display for SolutionViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolutionViolation::ArcCapacity {
+                scenario,
+                arc: (s, t),
+                load,
+                capacity,
+            } => write!(
+                f,
+                "scenario {scenario} puts load {load} on arc ({s}->{t}), but its capacity is {capacity}"
+            ),
+            SolutionViolation::SharedCapacity {
+                scenario,
+                arcs,
+                load,
+                capacity,
+            } => write!(
+                f,
+                "scenario {scenario} puts load {load} on arcs ({}) sharing capacity {capacity}",
+                arcs.iter()
+                    .map(|(s, t)| format!("{s}->{t}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SolutionViolation::VertexCapacity {
+                scenario,
+                vertex,
+                load,
+                capacity,
+            } => write!(
+                f,
+                "scenario {scenario} routes {load} supply units through vertex {vertex}, but its capacity is {capacity}"
+            ),
+            SolutionViolation::ExcessiveArcLoad {
+                scenario,
+                arc: (s, t),
+                load,
+                supply,
+            } => write!(
+                f,
+                "scenario {scenario} has {supply} supply, but puts load {load} on arc ({s}->{t})"
+            ),
+            SolutionViolation::FlowConservation {
+                scenario,
+                origin,
+                destination,
+                vertex,
+                imbalance,
+            } => write!(
+                f,
+                "in scenario {scenario}, the flow from {origin} to {destination} is not conserved at vertex {vertex}, where {} by {}",
+                match imbalance.is_positive() {
+                    true => "outflow exceeds inflow",
+                    false => "inflow exceeds outflow",
+                },
+                imbalance.abs()
+            ),
+            SolutionViolation::RemainingSupply {
+                scenario,
+                origin,
+                destination,
+                remaining,
+                supply,
+            } => write!(
+                f,
+                "scenario {scenario} has {remaining} supply units remaining from {origin} to {destination}, but only {supply} were demanded"
+            ),
+            SolutionViolation::DeliveredSupply {
+                scenario,
+                origin,
+                destination,
+                delivered,
+                expected,
+            } => write!(
+                f,
+                "scenario {scenario} delivers {delivered} supply units from {origin} to {destination}, but {expected} were routed according to the remaining supply"
+            ),
+            SolutionViolation::CommodityLoads {
+                scenario,
+                arc: (s, t),
+                commodity_loads,
+                arc_load,
+            } => write!(
+                f,
+                "in scenario {scenario}, the flows of all OD pairs put load {commodity_loads} on arc ({s}->{t}), but its arc load is {arc_load}"
+            ),
+            SolutionViolation::SlackBudget {
+                scenario,
+                slack,
+                budget,
+            } => write!(
+                f,
+                "scenario {scenario} uses {slack} slack, but its budget is {budget}"
+            ),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use clap::ValueEnum;
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    CostFunction, DeltaFunction, ObjectiveFunction, Options, RelativeDrawFunction,
+    RemainderSolveMethod, SlackFunction,
+};
+use crate::Result;
+
+// Config files use the same (kebab-case) names as the CLI, so that a value can be copied from
+// one to the other.
+macro_rules! serde_via_value_enum {
+    ($($t:ty),*) => {
+        $(
+            impl Serialize for $t {
+                fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    match self.to_possible_value() {
+                        Some(value) => serializer.serialize_str(value.get_name()),
+                        None => Err(S::Error::custom(format!(
+                            "{:?} cannot be stored in a config file",
+                            self
+                        ))),
+                    }
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $t {
+                fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let name = String::deserialize(deserializer)?;
+                    <$t as ValueEnum>::from_str(&name, true).map_err(D::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+serde_via_value_enum!(
+    CostFunction,
+    DeltaFunction,
+    RelativeDrawFunction,
+    SlackFunction,
+    RemainderSolveMethod,
+    ObjectiveFunction
+);
+
+impl Options {
+    pub fn from_file(filename: &str) -> Result<Self> {
+        let options_string = std::fs::read_to_string(filename)?;
+        let options: Options = serde_json::from_str(&options_string)?;
+        Ok(options)
+    }
+
+    pub fn serialize(&self, filename: &str) -> Result<()> {
+        let json_str = serde_json::to_string_pretty(self)?;
+        log::debug!("Writing\n{json_str}\nto {filename}");
+        std::fs::write(filename, json_str)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use clap::ValueEnum;
+use strum::Display;
+
+#[derive(ValueEnum, Debug, Clone, Display)]
+#[clap(rename_all = "kebab-case")]
+pub enum DisruptionMethod {
+    /// Fail every arc once, one per scenario (N-1)
+    #[value(name = "n-1")]
+    SingleArc,
+    /// Fail k randomly chosen arcs per scenario
+    KRandom,
+    /// Fail the k arcs closest to a randomly chosen vertex per scenario
+    Clustered,
+}
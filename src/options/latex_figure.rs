@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+use strum::Display;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[clap(rename_all = "kebab-case")]
+pub enum LatexFigure {
+    /// Vertices and arcs, labelled with their costs and capacities
+    Network,
+    /// One subfigure per scenario, with arcs labelled and drawn as thick as their loads
+    Scenarios,
+    /// Fixed arcs labelled and drawn as thick as their consistent flows
+    ConsistentFlows,
+    /// Total loads over all scenarios of the baseline and the solution side by side
+    Baseline,
+}
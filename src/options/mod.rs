@@ -1,22 +1,57 @@
+mod config;
 mod cost;
 mod delta;
+mod disruption;
+mod latex_figure;
+mod network_format;
+mod objective;
 mod relative_draw;
 mod slack;
 mod solve_method;
 
 pub use cost::CostFunction;
 pub use delta::DeltaFunction;
+pub use disruption::DisruptionMethod;
+pub use latex_figure::LatexFigure;
+pub use network_format::NetworkFormat;
+pub use objective::ObjectiveFunction;
 pub use relative_draw::RelativeDrawFunction;
 pub use slack::SlackFunction;
 pub use solve_method::RemainderSolveMethod;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Options {
     pub cost_fn: CostFunction,
     pub delta_fn: DeltaFunction,
     pub relative_draw_fn: RelativeDrawFunction,
     pub slack_fn: SlackFunction,
     pub remainder_solve_method: RemainderSolveMethod,
+
+    /// Cost metric the greedy routes by and in which the cost of a solution is reported
+    #[serde(default = "default_routing_metric")]
+    pub routing_metric: String,
+    #[serde(default)]
+    pub objective_fn: ObjectiveFunction,
+    /// Cost metrics minimized by lexicographic or weighted objectives. Empty means all of them.
+    #[serde(default)]
+    pub objective_metrics: Vec<String>,
+    /// Weights of the objective metrics. Empty means a weight of 1 for each.
+    #[serde(default)]
+    pub objective_weights: Vec<f64>,
+    /// Use continuous instead of integer flow variables in the ILPs. The flow of each OD pair is
+    /// rounded to whole quanta path by path afterwards, which keeps it conserved.
+    #[serde(default)]
+    pub continuous_flows: bool,
+    /// Directory in which preprocessing results are cached. Not a solver parameter, and thus
+    /// not part of config files.
+    #[serde(skip)]
+    pub preprocessing_cache: Option<String>,
+}
+
+fn default_routing_metric() -> String {
+    crate::network::PRIMARY_METRIC.to_string()
 }
 
 impl Default for Options {
@@ -27,6 +62,12 @@ impl Default for Options {
             relative_draw_fn: RelativeDrawFunction::Linear,
             slack_fn: SlackFunction::DifferenceToMax,
             remainder_solve_method: RemainderSolveMethod::None,
+            routing_metric: default_routing_metric(),
+            objective_fn: ObjectiveFunction::Single,
+            objective_metrics: vec![],
+            objective_weights: vec![],
+            continuous_flows: false,
+            preprocessing_cache: None,
         }
     }
 }
@@ -0,0 +1,13 @@
+use clap::ValueEnum;
+use strum::Display;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[clap(rename_all = "kebab-case")]
+pub enum NetworkFormat {
+    /// JSON with dense matrices
+    Dense,
+    /// JSON listing arcs and demands, which reference vertices by name
+    Sparse,
+    /// Compact binary format with sparse matrices
+    Binary,
+}
@@ -0,0 +1,12 @@
+use clap::ValueEnum;
+
+/// How the ILPs combine several cost metrics into a single objective. Lexicographic objectives
+/// minimize the metrics one after another, keeping the optima of earlier ones fixed.
+#[derive(ValueEnum, Debug, Clone, Default)]
+#[clap(rename_all = "kebab-case")]
+pub enum ObjectiveFunction {
+    #[default]
+    Single,
+    Lexicographic,
+    Weighted,
+}
@@ -1,5 +1,7 @@
 use std::{error::Error, fmt::Display};
 
+use crate::{InfeasibilityDiagnosis, SolutionViolation};
+
 pub type Result<T> = std::result::Result<T, SolverError>;
 
 #[derive(Debug)]
@@ -7,6 +9,10 @@ pub enum SolverError {
     NetworkIOError(std::io::Error),
     NetworkExportError(csv::Error),
     NetworkSerializationError(serde_json::Error),
+    NetworkBinaryFormatError(String),
+    SparseNetworkFormatError(String),
+    NetworkImportError(String),
+    DimacsFormatError(String),
     NetworkShapeError(String),
 
     FixedArcMemoryCorruptError,
@@ -17,11 +23,12 @@ pub enum SolverError {
     NoSlackLeftError(usize),
     GurobiOpsError(grb::Error),
     GurobiSolutionError(usize),
+    InfeasibleScenarioError(InfeasibilityDiagnosis),
 
     SkippedPreprocessingError,
     SkippedBaselineError,
     SkippedSolveError,
-    InvalidSolutionError(String),
+    InvalidSolutionError(SolutionViolation),
 }
 
 impl Display for SolverError {
@@ -34,6 +41,13 @@ impl Display for SolverError {
                 SolverError::NetworkExportError(e) => format!("Failed to export the network: {e}."),
                 SolverError::NetworkSerializationError(e) =>
                     format!("Failed to parse the network: {e}."),
+                SolverError::NetworkBinaryFormatError(e) =>
+                    format!("Failed to read or write the binary network: {e}."),
+                SolverError::SparseNetworkFormatError(e) =>
+                    format!("Failed to read or write the sparse network: {e}."),
+                SolverError::NetworkImportError(e) => format!("Failed to import the network: {e}."),
+                SolverError::DimacsFormatError(e) =>
+                    format!("Failed to read the DIMACS flow solution: {e}."),
                 SolverError::NetworkShapeError(e) => format!("Network is invalid: {e}"),
                 SolverError::FixedArcMemoryCorruptError =>
                     "The fixed arc memory is corrupted.".to_owned(),
@@ -48,6 +62,7 @@ impl Display for SolverError {
                 SolverError::GurobiOpsError(e) => format!("Gurobi encountered an error: {e}."),
                 SolverError::GurobiSolutionError(e) =>
                     format!("Gurobi could not find a feasible flow in scenario {e}."),
+                SolverError::InfeasibleScenarioError(diagnosis) => diagnosis.to_string(),
                 SolverError::SkippedPreprocessingError =>
                     "No auxiliary network found. Forgot to preprocess?".to_owned(),
                 SolverError::SkippedBaselineError =>
@@ -70,6 +85,36 @@ impl From<serde_json::Error> for SolverError {
     }
 }
 
+impl From<rmp_serde::decode::Error> for SolverError {
+    fn from(value: rmp_serde::decode::Error) -> Self {
+        SolverError::NetworkBinaryFormatError(value.to_string())
+    }
+}
+
+impl From<rmp_serde::encode::Error> for SolverError {
+    fn from(value: rmp_serde::encode::Error) -> Self {
+        SolverError::NetworkBinaryFormatError(value.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for SolverError {
+    fn from(value: zip::result::ZipError) -> Self {
+        SolverError::NetworkImportError(value.to_string())
+    }
+}
+
+impl From<quick_xml::Error> for SolverError {
+    fn from(value: quick_xml::Error) -> Self {
+        SolverError::NetworkImportError(value.to_string())
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for SolverError {
+    fn from(value: quick_xml::events::attributes::AttrError) -> Self {
+        SolverError::NetworkImportError(value.to_string())
+    }
+}
+
 impl From<std::io::Error> for SolverError {
     fn from(value: std::io::Error) -> Self {
         SolverError::NetworkIOError(value)